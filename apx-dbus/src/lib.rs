@@ -0,0 +1,120 @@
+//! Apx stacks, subsystems and package managers on the session bus, for panel applets and
+//! launcher plugins that shouldn't embed apx-shim or start the GUI.
+
+mod pkgmanagers;
+mod stacks;
+mod subsystems;
+
+use apx_shim::podman::Podman;
+use std::time::Duration;
+use tracing::{debug, warn};
+use zbus::{connection::Builder, Connection};
+
+pub use pkgmanagers::{PackageManagerInfo, PackageManagers};
+pub use stacks::{StackInfo, Stacks};
+pub use subsystems::{SubsystemInfo, Subsystems};
+
+pub const BUS_NAME: &str = "com.singularityos.Apx1";
+pub const OBJECT_PATH: &str = "/com/singularityos/Apx1";
+
+// How often subsystems are re-read when podman events can't be followed.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+// Container actions that can change a subsystem's status. Execs, which happen on every
+// `apx enter`, are left out.
+const STATUS_ACTIONS: [&str; 9] = [
+    "create", "init", "start", "restart", "stop", "die", "died", "remove", "unpause",
+];
+
+/// Adds the interfaces to `builder` at [`OBJECT_PATH`]. Requesting [`BUS_NAME`] is left to
+/// the caller, so the service can be served on a private bus too.
+pub fn serve_at(builder: Builder<'_>) -> zbus::Result<Builder<'_>> {
+    builder
+        .serve_at(OBJECT_PATH, Subsystems::default())?
+        .serve_at(OBJECT_PATH, Stacks)?
+        .serve_at(OBJECT_PATH, PackageManagers)
+}
+
+/// Emits subsystem status signals as containers change, following podman's events and
+/// polling when they aren't available. Runs until the connection goes away.
+pub async fn watch_status(connection: &Connection) -> anyhow::Result<()> {
+    let iface = connection
+        .object_server()
+        .interface::<_, Subsystems>(OBJECT_PATH)
+        .await?;
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+    // Podman events block between events, so they're read on their own thread.
+    std::thread::spawn(move || {
+        let events = match Podman::new().events() {
+            Ok(events) => events,
+            Err(e) => {
+                warn!("Could not follow podman events: {e}");
+                return;
+            }
+        };
+
+        for event in events {
+            match event {
+                Ok(event) => {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    warn!("Stopped following podman events: {e}");
+                    return;
+                }
+            }
+        }
+    });
+
+    let mut events = Some(rx);
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        if let Err(e) = iface.get().await.refresh(iface.signal_emitter()).await {
+            warn!("Could not refresh subsystems: {e}");
+        }
+
+        // Wait for something worth re-reading the subsystems for.
+        loop {
+            match events.as_mut() {
+                Some(rx) => match rx.recv().await {
+                    Some(event) if STATUS_ACTIONS.contains(&event.action.as_str()) => {
+                        debug!("container {} {}", event.name, event.action);
+                        break;
+                    }
+                    Some(_) => {}
+                    None => {
+                        warn!("Podman events ended, polling for status changes instead");
+                        events = None;
+                    }
+                },
+                None => {
+                    poll.tick().await;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Runs a blocking apx-shim call on its own thread, reporting errors over D-Bus. Method
+// calls run on zbus' executor rather than tokio's, so tokio's blocking pool isn't used.
+pub(crate) async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> zbus::fdo::Result<T> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+
+    match rx.await {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => Err(zbus::fdo::Error::Failed(e.to_string())),
+        Err(e) => Err(zbus::fdo::Error::Failed(e.to_string())),
+    }
+}
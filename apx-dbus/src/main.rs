@@ -0,0 +1,15 @@
+use tracing::info;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt().init();
+
+    let connection = apx_dbus::serve_at(zbus::connection::Builder::session()?)?
+        .name(apx_dbus::BUS_NAME)?
+        .build()
+        .await?;
+
+    info!("Serving {} on the session bus", apx_dbus::BUS_NAME);
+
+    apx_dbus::watch_status(&connection).await
+}
@@ -0,0 +1,59 @@
+use crate::blocking;
+use apx_shim::PackageManager;
+use serde::{Deserialize, Serialize};
+use zbus::{fdo, interface, object_server::SignalEmitter, zvariant::Type};
+
+/// A package manager as sent over the bus.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq, Eq)]
+pub struct PackageManagerInfo {
+    pub name: String,
+    pub need_sudo: bool,
+    pub built_in: bool,
+}
+
+impl From<PackageManager> for PackageManagerInfo {
+    fn from(p: PackageManager) -> Self {
+        Self {
+            name: p.name,
+            need_sudo: p.need_sudo,
+            built_in: p.built_in,
+        }
+    }
+}
+
+/// `com.singularityos.Apx1.PackageManagers`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PackageManagers;
+
+#[interface(name = "com.singularityos.Apx1.PackageManagers")]
+impl PackageManagers {
+    async fn list(&self) -> fdo::Result<Vec<PackageManagerInfo>> {
+        blocking(|| {
+            Ok(PackageManager::get_all()?
+                .into_iter()
+                .map(PackageManagerInfo::from)
+                .collect())
+        })
+        .await
+    }
+
+    async fn get(&self, name: String) -> fdo::Result<PackageManagerInfo> {
+        blocking(move || Ok(PackageManager::get(&name)?.into())).await
+    }
+
+    async fn remove(
+        &self,
+        name: String,
+        force: bool,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        blocking(move || PackageManager::get(&name)?.remove(force)).await?;
+
+        Self::changed(&emitter).await?;
+        Ok(())
+    }
+
+    /// Package managers were created, updated or removed.
+    #[zbus(signal)]
+    async fn changed(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
@@ -0,0 +1,115 @@
+use crate::blocking;
+use anyhow::anyhow;
+use apx_shim::Stack;
+use serde::{Deserialize, Serialize};
+use zbus::{fdo, interface, object_server::SignalEmitter, zvariant::Type};
+
+/// A stack as sent over the bus.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq, Eq)]
+pub struct StackInfo {
+    pub name: String,
+    pub base: String,
+    pub package_manager: String,
+    pub packages: Vec<String>,
+    pub built_in: bool,
+}
+
+impl From<Stack> for StackInfo {
+    fn from(s: Stack) -> Self {
+        Self {
+            name: s.name,
+            base: s.base,
+            package_manager: s.package_manager,
+            packages: s.packages,
+            built_in: s.built_in,
+        }
+    }
+}
+
+fn find(name: &str) -> anyhow::Result<Stack> {
+    Stack::get_all()?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| anyhow!("No stack named {name}"))
+}
+
+/// `com.singularityos.Apx1.Stacks`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stacks;
+
+#[interface(name = "com.singularityos.Apx1.Stacks")]
+impl Stacks {
+    async fn list(&self) -> fdo::Result<Vec<StackInfo>> {
+        blocking(|| Ok(Stack::get_all()?.into_iter().map(StackInfo::from).collect())).await
+    }
+
+    async fn get(&self, name: String) -> fdo::Result<StackInfo> {
+        blocking(move || Ok(find(&name)?.into())).await
+    }
+
+    async fn create(
+        &self,
+        name: String,
+        base: String,
+        package_manager: String,
+        packages: Vec<String>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<StackInfo> {
+        let created = blocking(move || {
+            let mut stack = Stack {
+                name,
+                base,
+                packages,
+                package_manager,
+                built_in: false,
+            };
+            stack.create()?;
+
+            Ok(StackInfo::from(stack))
+        })
+        .await?;
+
+        Self::changed(&emitter).await?;
+        Ok(created)
+    }
+
+    async fn update(
+        &self,
+        name: String,
+        base: String,
+        package_manager: String,
+        packages: Vec<String>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        blocking(move || {
+            Stack {
+                name,
+                base,
+                packages,
+                package_manager,
+                built_in: false,
+            }
+            .update()
+        })
+        .await?;
+
+        Self::changed(&emitter).await?;
+        Ok(())
+    }
+
+    async fn remove(
+        &self,
+        name: String,
+        force: bool,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        blocking(move || find(&name)?.remove(force)).await?;
+
+        Self::changed(&emitter).await?;
+        Ok(())
+    }
+
+    /// Stacks were created, updated or removed.
+    #[zbus(signal)]
+    async fn changed(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
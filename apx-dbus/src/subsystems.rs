@@ -0,0 +1,238 @@
+use crate::blocking;
+use anyhow::anyhow;
+use apx_shim::{podman::Podman, Stack, Subsystem};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use tracing::debug;
+use zbus::{fdo, interface, object_server::SignalEmitter, zvariant::Type};
+
+/// A subsystem as sent over the bus.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq, Eq)]
+pub struct SubsystemInfo {
+    pub name: String,
+    /// The podman container backing the subsystem.
+    pub internal_name: String,
+    pub stack: String,
+    pub home: String,
+    pub status: String,
+    pub running: bool,
+}
+
+impl From<&Subsystem> for SubsystemInfo {
+    fn from(s: &Subsystem) -> Self {
+        Self {
+            name: s.name.clone(),
+            internal_name: s.internal_name.clone(),
+            stack: s.stack.name.clone(),
+            home: s.home.clone(),
+            status: s.status.clone(),
+            running: s.running(),
+        }
+    }
+}
+
+fn find(name: &str) -> anyhow::Result<Subsystem> {
+    Subsystem::get_all()?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| anyhow!("No subsystem named {name}"))
+}
+
+/// `com.singularityos.Apx1.Subsystems`
+#[derive(Clone, Debug)]
+pub struct Subsystems {
+    /// Statuses as last reported, to tell what changed.
+    known: Arc<Mutex<Option<HashMap<String, SubsystemInfo>>>>,
+    /// Where statuses are read from, rather than apx.
+    podman: Podman,
+}
+
+impl Default for Subsystems {
+    fn default() -> Self {
+        Self::new(Podman::new())
+    }
+}
+
+impl Subsystems {
+    pub fn new(podman: Podman) -> Self {
+        Self {
+            known: Arc::default(),
+            podman,
+        }
+    }
+
+    // The named subsystem, with its status read from podman.
+    async fn find_with_status(&self, name: String) -> fdo::Result<SubsystemInfo> {
+        let podman = self.podman.clone();
+
+        blocking(move || {
+            let mut subsystem = find(&name)?;
+            if let Err(e) = subsystem.refresh_status(&podman) {
+                debug!("Could not read the status of {name} from podman: {e}");
+            }
+
+            Ok(SubsystemInfo::from(&subsystem))
+        })
+        .await
+    }
+
+    /// Re-reads the subsystems, emitting signals for whatever changed since the last read.
+    pub async fn refresh(&self, emitter: &SignalEmitter<'_>) -> fdo::Result<Vec<SubsystemInfo>> {
+        let podman = self.podman.clone();
+        let all: Vec<SubsystemInfo> = blocking(move || {
+            Ok(Subsystem::get_all_with_status(&podman)?
+                .iter()
+                .map(SubsystemInfo::from)
+                .collect())
+        })
+        .await?;
+
+        let current: HashMap<String, SubsystemInfo> =
+            all.iter().map(|s| (s.name.clone(), s.clone())).collect();
+
+        let previous = self
+            .known
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .replace(current.clone());
+
+        // The first read has nothing to compare against.
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return Ok(all),
+        };
+
+        for name in previous.keys().filter(|n| !current.contains_key(*n)) {
+            Self::removed(emitter, name).await?;
+        }
+
+        for info in &all {
+            match previous.get(&info.name) {
+                None => Self::added(emitter, &info.name).await?,
+                Some(old) if old.status != info.status || old.running != info.running => {
+                    Self::status_changed(emitter, &info.name, &info.status, info.running).await?
+                }
+                Some(_) => {}
+            }
+        }
+
+        Ok(all)
+    }
+
+    // Runs `action` on the named subsystem, then reports the status it left behind.
+    async fn act(
+        &self,
+        name: String,
+        emitter: &SignalEmitter<'_>,
+        action: impl FnOnce(Subsystem) -> anyhow::Result<()> + Send + 'static,
+    ) -> fdo::Result<()> {
+        blocking(move || action(find(&name)?)).await?;
+        self.refresh(emitter).await?;
+
+        Ok(())
+    }
+}
+
+#[interface(name = "com.singularityos.Apx1.Subsystems")]
+impl Subsystems {
+    async fn list(
+        &self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<Vec<SubsystemInfo>> {
+        self.refresh(&emitter).await
+    }
+
+    async fn get(&self, name: String) -> fdo::Result<SubsystemInfo> {
+        self.find_with_status(name).await
+    }
+
+    /// Creates a subsystem from a stack. An empty `home` uses apx's default.
+    async fn create(
+        &self,
+        name: String,
+        stack: String,
+        home: String,
+        init: bool,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<SubsystemInfo> {
+        let created = blocking(move || {
+            let stack = Stack::get_all()?
+                .into_iter()
+                .find(|s| s.name == stack)
+                .ok_or_else(|| anyhow!("No stack named {stack}"))?;
+
+            let mut subsystem = Subsystem {
+                internal_name: String::new(),
+                name,
+                stack,
+                home,
+                status: String::new(),
+                has_init: init,
+            };
+            subsystem.create()?;
+
+            Ok(SubsystemInfo::from(&subsystem))
+        })
+        .await?;
+
+        self.refresh(&emitter).await?;
+        Ok(created)
+    }
+
+    async fn start(
+        &self,
+        name: String,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.act(name, &emitter, |s| s.start()).await
+    }
+
+    async fn stop(
+        &self,
+        name: String,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.act(name, &emitter, |s| s.stop()).await
+    }
+
+    async fn reset(
+        &self,
+        name: String,
+        force: bool,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.act(name, &emitter, move |s| s.reset(force)).await
+    }
+
+    async fn remove(
+        &self,
+        name: String,
+        force: bool,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.act(name, &emitter, move |s| s.remove(force)).await
+    }
+
+    async fn install(&self, name: String, packages: Vec<String>) -> fdo::Result<()> {
+        blocking(move || find(&name)?.install(&packages)).await
+    }
+
+    async fn remove_packages(&self, name: String, packages: Vec<String>) -> fdo::Result<()> {
+        blocking(move || find(&name)?.remove_packages(&packages)).await
+    }
+
+    #[zbus(signal)]
+    async fn status_changed(
+        emitter: &SignalEmitter<'_>,
+        name: &str,
+        status: &str,
+        running: bool,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn added(emitter: &SignalEmitter<'_>, name: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn removed(emitter: &SignalEmitter<'_>, name: &str) -> zbus::Result<()>;
+}
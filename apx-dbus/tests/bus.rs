@@ -0,0 +1,206 @@
+//! Serves the interfaces on a private bus, with stand-in apx and podman scripts behind them.
+
+use apx_dbus::SubsystemInfo;
+use apx_shim::command::{set_apx_bin, set_execution_strategy, ExecutionStrategy};
+use futures_util::{Stream, StreamExt};
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+use zbus::{connection, proxy, Connection};
+
+#[proxy(
+    interface = "com.singularityos.Apx1.Subsystems",
+    default_service = "com.singularityos.Apx1",
+    default_path = "/com/singularityos/Apx1"
+)]
+trait Subsystems {
+    fn list(&self) -> zbus::Result<Vec<SubsystemInfo>>;
+
+    fn get(&self, name: &str) -> zbus::Result<SubsystemInfo>;
+
+    fn create(&self, name: &str, stack: &str, home: &str, init: bool) -> zbus::Result<SubsystemInfo>;
+
+    fn start(&self, name: &str) -> zbus::Result<()>;
+
+    fn remove(&self, name: &str, force: bool) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn status_changed(&self, name: String, status: String, running: bool) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn added(&self, name: String) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn removed(&self, name: String) -> zbus::Result<()>;
+}
+
+// Subsystems are files under `subsystems/`, container statuses files under `status/`.
+const APX: &str = r#"stack='{"Name":"fedora","Base":"fedora:40","Packages":[],"PkgManager":"dnf","BuiltIn":true}'
+case "$1 $2" in
+"stacks list") echo "[$stack]" ;;
+"subsystems list")
+    sep=''
+    printf '['
+    for f in "$dir"/subsystems/*; do
+        [ -e "$f" ] || continue
+        name=$(basename "$f")
+        printf '%s{"InternalName":"apx-%s","Name":"%s","Stack":%s,"Home":"","Status":"Up"}' "$sep" "$name" "$name" "$stack"
+        sep=','
+    done
+    echo ']' ;;
+"subsystems new") touch "$dir/subsystems/$4" ;;
+"subsystems rm") rm -f "$dir/subsystems/$4" "$dir/status/apx-$4" ;;
+*)
+    case "$2" in
+    start) echo running > "$dir/status/apx-$1" ;;
+    stop) echo exited > "$dir/status/apx-$1" ;;
+    *) echo "unknown command: $*" >&2; exit 1 ;;
+    esac ;;
+esac"#;
+
+const PODMAN: &str = r#"[ "$1 $2" = "container inspect" ] || exit 125
+status=$(cat "$dir/status/$3" 2>/dev/null || echo created)
+running=false
+[ "$status" = running ] && running=true
+echo "[{\"State\":{\"Status\":\"$status\",\"Running\":$running}}]""#;
+
+// A dbus-daemon of our own, killed when dropped.
+struct PrivateBus {
+    daemon: Child,
+    address: String,
+}
+
+impl PrivateBus {
+    // None when dbus-daemon isn't installed.
+    fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+
+    fn builder(&self) -> connection::Builder<'_> {
+        connection::Builder::address(self.address.as_str()).unwrap()
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+// Where the stand-ins keep their state, removed when dropped.
+struct StandIns(PathBuf);
+
+impl StandIns {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("apx-dbus-test-{}", std::process::id()));
+
+        for sub in ["subsystems", "status"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+
+        for (name, body) in [("apx", APX), ("podman", PODMAN)] {
+            let path = dir.join(name);
+            std::fs::write(&path, format!("#!/bin/sh\ndir='{}'\n{body}\n", dir.display())).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        Self(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for StandIns {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+async fn next<T>(stream: &mut (impl Stream<Item = T> + Unpin)) -> T {
+    tokio::time::timeout(Duration::from_secs(10), stream.next())
+        .await
+        .expect("timed out waiting for a signal")
+        .expect("the signal stream ended")
+}
+
+#[tokio::test]
+async fn subsystems_are_served_with_status_signals() {
+    let Some(bus) = PrivateBus::start() else {
+        eprintln!("dbus-daemon isn't installed, skipping");
+        return;
+    };
+
+    let stand_ins = StandIns::new();
+    std::fs::write(stand_ins.path().join("subsystems/box"), "").unwrap();
+    std::fs::write(stand_ins.path().join("status/apx-box"), "exited").unwrap();
+
+    // No podman socket, so statuses come from the stand-in podman CLI.
+    std::env::remove_var("CONTAINER_HOST");
+    std::env::set_var("XDG_RUNTIME_DIR", stand_ins.path());
+    let path = std::env::var("PATH").unwrap_or_default();
+    std::env::set_var("PATH", format!("{}:{path}", stand_ins.path().display()));
+
+    set_execution_strategy(ExecutionStrategy::Direct);
+    set_apx_bin(Some(stand_ins.path().join("apx").display().to_string()));
+
+    let _service: Connection = apx_dbus::serve_at(bus.builder())
+        .unwrap()
+        .name(apx_dbus::BUS_NAME)
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    let client = bus.builder().build().await.unwrap();
+    let subsystems = SubsystemsProxy::new(&client).await.unwrap();
+
+    let mut status_changed = subsystems.receive_status_changed().await.unwrap();
+    let mut added = subsystems.receive_added().await.unwrap();
+    let mut removed = subsystems.receive_removed().await.unwrap();
+
+    let listed = subsystems.list().await.unwrap();
+    assert_eq!(
+        listed,
+        vec![SubsystemInfo {
+            name: "box".into(),
+            internal_name: "apx-box".into(),
+            stack: "fedora".into(),
+            home: String::new(),
+            status: "exited".into(),
+            running: false,
+        }]
+    );
+
+    subsystems.start("box").await.unwrap();
+    let signal = next(&mut status_changed).await;
+    let args = signal.args().unwrap();
+    assert_eq!((args.name.as_str(), args.status.as_str(), args.running), ("box", "running", true));
+    assert!(subsystems.get("box").await.unwrap().running);
+
+    let created = subsystems.create("dev", "fedora", "", false).await.unwrap();
+    assert_eq!(created.name, "dev");
+    assert_eq!(next(&mut added).await.args().unwrap().name, "dev");
+
+    subsystems.remove("dev", true).await.unwrap();
+    assert_eq!(next(&mut removed).await.args().unwrap().name, "dev");
+
+    assert!(subsystems.get("dev").await.is_err());
+    assert!(subsystems.start("missing").await.is_err());
+}
@@ -1,21 +1,107 @@
 use crate::error::ApxError;
+use crate::{history, privilege};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::sync::RwLock;
 use tracing::{debug, warn};
 use which::which;
 
+/// How apx is reached from this process.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExecutionStrategy {
+    /// Detect flatpak and container sandboxes and pick a host spawner to match.
+    #[default]
+    Auto,
+    /// Run apx directly.
+    Direct,
+    /// Run apx on the host through `host-spawn`.
+    HostSpawn,
+    /// Run apx on the host through `flatpak-spawn --host`.
+    FlatpakSpawn,
+}
+
+impl ExecutionStrategy {
+    pub const ALL: [ExecutionStrategy; 4] = [
+        ExecutionStrategy::Auto,
+        ExecutionStrategy::Direct,
+        ExecutionStrategy::HostSpawn,
+        ExecutionStrategy::FlatpakSpawn,
+    ];
+}
+
+static EXECUTION_STRATEGY: RwLock<ExecutionStrategy> = RwLock::new(ExecutionStrategy::Auto);
+
+/// Overrides how apx is reached, e.g. when sandbox detection gets it wrong.
+pub fn set_execution_strategy(strategy: ExecutionStrategy) {
+    match EXECUTION_STRATEGY.write() {
+        Ok(mut s) => *s = strategy,
+        Err(e) => warn!("Could not set execution strategy: {}", e),
+    }
+}
+
+/// The execution strategy currently in use.
+pub fn execution_strategy() -> ExecutionStrategy {
+    match EXECUTION_STRATEGY.read() {
+        Ok(s) => *s,
+        Err(_) => ExecutionStrategy::Auto,
+    }
+}
+
 // Finds out if we're running inside a container or on the host.
 fn running_in_container() -> bool {
     Path::new("/run/.containerenv").exists()
 }
 
+// Finds out if we're running inside a flatpak sandbox.
+fn running_in_flatpak() -> bool {
+    Path::new("/.flatpak-info").exists()
+}
+
+// The command prefix needed to run something on the host, if we're sandboxed.
+pub(crate) fn host_spawn_prefix() -> Option<String> {
+    match execution_strategy() {
+        ExecutionStrategy::Direct => None,
+        ExecutionStrategy::HostSpawn => Some(get_host_spawn_bin()),
+        ExecutionStrategy::FlatpakSpawn => Some("flatpak-spawn --host".into()),
+        ExecutionStrategy::Auto => {
+            if running_in_flatpak() {
+                Some("flatpak-spawn --host".into())
+            } else if running_in_container() {
+                Some(get_host_spawn_bin())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+static APX_BIN: RwLock<Option<String>> = RwLock::new(None);
+
+/// Overrides the apx binary, e.g. for a build installed outside `PATH`.
+/// Passing `None` goes back to looking it up.
+pub fn set_apx_bin(bin: Option<String>) {
+    match APX_BIN.write() {
+        Ok(mut b) => *b = bin,
+        Err(e) => warn!("Could not set apx binary: {}", e),
+    }
+}
+
 // Finds the apx binary path, solving for containerised runs.
 pub fn get_apx_bin() -> String {
-    if running_in_container() {
-        let host_spawn = get_host_spawn_bin();
-        let apx = format!("{host_spawn} apx");
-        return apx;
+    let bin = match APX_BIN.read() {
+        Ok(b) => b.clone(),
+        Err(_) => None,
+    };
+
+    if let Some(prefix) = host_spawn_prefix() {
+        return format!("{prefix} {}", bin.as_deref().unwrap_or("apx"));
+    }
+
+    if let Some(bin) = bin {
+        return bin;
     }
 
     match which("apx") {
@@ -45,9 +131,179 @@ pub fn run_apx(args: &str, ignore_errors: bool) -> Result<String> {
     return run_command(format!("{apx_bin} {args}"), ignore_errors);
 }
 
+// Runs an apx command, handing each line of output to `on_line` as it arrives.
+pub fn run_apx_with_progress(args: &str, mut on_line: impl FnMut(&str)) -> Result<String> {
+    let apx_bin = get_apx_bin();
+
+    debug!("apx bin resolved from: {}", apx_bin);
+
+    let command = format!("{apx_bin} {args} 2>&1");
+    let id = history::start(&command);
+    let mut output = String::new();
+
+    let status = (|| {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines() {
+                let line = line?;
+                on_line(&line);
+                output.push_str(&line);
+                output.push('\n');
+            }
+        }
+
+        child.wait()
+    })();
+
+    match status {
+        Ok(status) => {
+            history::finish(id, status.code(), &output, "");
+
+            if status.success() {
+                Ok(output)
+            } else {
+                Err(ApxError::CommandError { error: output }.into())
+            }
+        }
+        Err(e) => {
+            history::finish(id, None, &output, &e.to_string());
+            Err(e.into())
+        }
+    }
+}
+
+// Starts an apx command in the background without waiting on it, e.g. to launch an app.
+pub fn spawn_apx(args: &str) -> Result<()> {
+    let apx_bin = get_apx_bin();
+
+    debug!("spawning: {} {}", apx_bin, args);
+
+    let status = run_recorded(
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("{apx_bin} {args} >/dev/null 2>&1 &")),
+    )?
+    .status;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ApxError::CommandError {
+            error: format!("Could not start apx {args}"),
+        }
+        .into())
+    }
+}
+
+// Quotes a value for use inside `sh -c`.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+// Runs a command inside a subsystem through the privilege helper, the way apx runs
+// package managers that need sudo. Only the command is elevated, in the container;
+// apx and podman keep running as the user on the host.
+pub fn run_in_subsystem_elevated(subsystem: &str, command: &str, prompt: &str) -> Result<String> {
+    let helper = privilege::helper_command(prompt)?;
+    let program = privilege::helper_program(&helper);
+
+    if !subsystem_has(subsystem, program) {
+        return Err(ApxError::HelperNotFound {
+            helper: program.to_string(),
+        }
+        .into());
+    }
+
+    let command = format!(
+        "{} {} run -- {helper} {command}",
+        get_apx_bin(),
+        shell_quote(subsystem)
+    );
+
+    debug!("elevated command: {}", command);
+
+    let output = run_recorded(Command::new("sh").arg("-c").arg(command))?;
+
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).into());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    match privilege::elevation_error(output.status.code(), &stderr, &helper) {
+        Some(e) => Err(e.into()),
+        None => Err(ApxError::CommandError {
+            error: stderr.into(),
+        }
+        .into()),
+    }
+}
+
+// Whether `program` can be found inside the subsystem.
+fn subsystem_has(subsystem: &str, program: &str) -> bool {
+    let lookup = shell_quote(&format!("command -v {}", shell_quote(program)));
+    let command = format!(
+        "{} {} run -- sh -c {lookup}",
+        get_apx_bin(),
+        shell_quote(subsystem)
+    );
+
+    match Command::new("sh").arg("-c").arg(command).output() {
+        Ok(out) => out.status.success(),
+        Err(e) => {
+            warn!("Could not look up {} in {}: {}", program, subsystem, e);
+            false
+        }
+    }
+}
+
+// Runs an apx package manager command for a subsystem, or, for a package manager that
+// needs rights, its `elevated` command line inside the subsystem through the privilege
+// helper. apx's own sudo can't ask for a password, the helper can.
+pub fn run_apx_privileged(
+    args: &str,
+    subsystem: &str,
+    elevated: Option<&str>,
+    prompt: &str,
+) -> Result<String> {
+    match elevated {
+        Some(elevated) => run_in_subsystem_elevated(subsystem, elevated, prompt),
+        None => run_apx(args, false),
+    }
+}
+
+// Runs a command to completion, recording it in the history.
+pub(crate) fn run_recorded(command: &mut Command) -> std::io::Result<Output> {
+    let line = command
+        .get_args()
+        .last()
+        .map(|a| a.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let id = history::start(&line);
+    let output = command.output();
+
+    match &output {
+        Ok(out) => history::finish(
+            id,
+            out.status.code(),
+            &String::from_utf8_lossy(&out.stdout),
+            &String::from_utf8_lossy(&out.stderr),
+        ),
+        Err(e) => history::finish(id, None, "", &e.to_string()),
+    }
+
+    output
+}
+
 // Runs a shell command.
-fn run_command(args: String, ignore_errors: bool) -> Result<String> {
-    let output = Command::new("sh").arg("-c").arg(args).output();
+pub(crate) fn run_command(args: String, ignore_errors: bool) -> Result<String> {
+    let output = run_recorded(Command::new("sh").arg("-c").arg(args));
 
     match output {
         Ok(out) => {
@@ -72,3 +328,131 @@ fn run_command(args: String, ignore_errors: bool) -> Result<String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{lock_globals, ScratchDir};
+
+    // An apx that runs `<subsystem> run -- <command>` directly and fails everything
+    // else the way apx does when sudo in the container can't ask for a password.
+    const APX: &str = r#"if [ "$2" = run ]; then shift 3; exec "$@"; fi
+echo "sudo: a terminal is required to read the password" >&2
+exit 1"#;
+
+    fn stand_ins(helper: &str) -> (std::sync::MutexGuard<'static, ()>, ScratchDir) {
+        let guard = lock_globals();
+        let dir = ScratchDir::new();
+
+        set_execution_strategy(ExecutionStrategy::Direct);
+        set_apx_bin(Some(dir.script("apx", APX)));
+        privilege::set_helper(Some(format!(
+            "{} -p {{prompt}}",
+            dir.script("helper", helper)
+        )));
+
+        (guard, dir)
+    }
+
+    fn apx_error(result: Result<String>) -> ApxError {
+        result
+            .expect_err("the command should fail")
+            .downcast::<ApxError>()
+            .expect("an ApxError")
+    }
+
+    #[test]
+    fn elevated_command_runs_through_helper_with_prompt() {
+        let (_guard, _dir) = stand_ins(r#"echo "prompt: $2"; shift 2; exec "$@""#);
+
+        let output = run_in_subsystem_elevated("box", "echo installed", "Let me in").unwrap();
+
+        assert_eq!(output, "prompt: Let me in\ninstalled\n");
+    }
+
+    #[test]
+    fn dismissed_helper_is_authorization_denied() {
+        let (_guard, dir) = stand_ins("exit 126");
+
+        match apx_error(run_in_subsystem_elevated("box", "true", "Let me in")) {
+            ApxError::AuthorizationDenied { helper } => {
+                assert_eq!(helper, dir.path().join("helper").display().to_string())
+            }
+            e => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn sudo_refusal_is_authorization_denied() {
+        let (_guard, _dir) = stand_ins(r#"echo "sudo: a password is required" >&2; exit 1"#);
+
+        assert!(matches!(
+            apx_error(run_in_subsystem_elevated("box", "true", "Let me in")),
+            ApxError::AuthorizationDenied { .. }
+        ));
+    }
+
+    #[test]
+    fn pkexec_refusal_is_authorization_denied() {
+        let (_guard, _dir) = stand_ins(
+            r#"echo "Error executing command as another user: Not authorized" >&2; exit 127"#,
+        );
+
+        assert!(matches!(
+            apx_error(run_in_subsystem_elevated("box", "true", "Let me in")),
+            ApxError::AuthorizationDenied { .. }
+        ));
+    }
+
+    #[test]
+    fn missing_helper_is_not_run() {
+        let (_guard, dir) = stand_ins("true");
+        let missing = format!("{}/missing", dir.path().display());
+        privilege::set_helper(Some(missing.clone()));
+
+        match apx_error(run_in_subsystem_elevated("box", "true", "Let me in")) {
+            ApxError::HelperNotFound { helper } => assert_eq!(helper, missing),
+            e => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn missing_command_is_a_command_error() {
+        let (_guard, _dir) = stand_ins(r#"shift 2; exec "$@""#);
+
+        assert!(matches!(
+            apx_error(run_in_subsystem_elevated("box", "no-such-command", "")),
+            ApxError::CommandError { .. }
+        ));
+    }
+
+    #[test]
+    fn failing_command_is_a_command_error() {
+        let (_guard, _dir) = stand_ins(r#"shift 2; exec "$@""#);
+
+        match apx_error(run_in_subsystem_elevated("box", "sh -c 'echo broken >&2; exit 3'", "")) {
+            ApxError::CommandError { error } => assert_eq!(error, "broken\n"),
+            e => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn privileged_command_runs_through_helper_when_elevated() {
+        let (_guard, _dir) = stand_ins(r#"shift 2; exec "$@""#);
+
+        let output = run_apx_privileged(
+            "box install 'vim'",
+            "box",
+            Some("echo apt install 'vim'"),
+            "Let me in",
+        )
+        .unwrap();
+
+        assert_eq!(output, "apt install vim\n");
+
+        assert!(matches!(
+            apx_error(run_apx_privileged("box install 'vim'", "box", None, "Let me in")),
+            ApxError::CommandError { .. }
+        ));
+    }
+}
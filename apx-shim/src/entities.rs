@@ -1,10 +1,18 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use tracing::debug;
 
-use crate::{command::run_apx, error::ApxError};
+use crate::{
+    podman::{ContainerState, ContainerStats, Podman},
+    command::{
+        run_apx, run_apx_privileged, run_apx_with_progress, shell_quote,
+        spawn_apx,
+    },
+    error::ApxError,
+};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Stack {
     #[serde(alias = "Name")]
     pub name: String,
@@ -31,11 +39,11 @@ impl Stack {
     pub fn create(&mut self) -> Result<()> {
         run_apx(
             &format!(
-                "stacks new --name {} --base {} --packages {} --pkg-manager {}",
-                self.name,
-                self.base,
-                self.packages.join(" "),
-                self.package_manager
+                "stacks new --no-prompt --name {} --base {} --packages {} --pkg-manager {}",
+                shell_quote(&self.name),
+                shell_quote(&self.base),
+                shell_quote(&self.packages.join(" ")),
+                shell_quote(&self.package_manager)
             ),
             false,
         )?;
@@ -62,11 +70,11 @@ impl Stack {
     pub fn update(&self) -> Result<()> {
         let res = run_apx(
             &format!(
-                "stacks update --name {} --base {} --packages {} --pkg-manager {}",
-                self.name,
-                self.base,
-                self.packages.join(" "),
-                self.package_manager
+                "stacks update --no-prompt --name {} --base {} --packages {} --pkg-manager {}",
+                shell_quote(&self.name),
+                shell_quote(&self.base),
+                shell_quote(&self.packages.join(" ")),
+                shell_quote(&self.package_manager)
             ),
             false,
         );
@@ -78,11 +86,10 @@ impl Stack {
     }
 
     pub fn remove(&self, force: bool) -> Result<()> {
-        let mut command = format!("stacks rm --name {}", self.name);
-
-        if force {
-            command.push_str(" --force");
-        }
+        let command = with_force(
+            format!("stacks rm --name {}", shell_quote(&self.name)),
+            force,
+        );
 
         let res = run_apx(&command, false);
 
@@ -93,7 +100,7 @@ impl Stack {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Subsystem {
     #[serde(alias = "InternalName")]
     pub internal_name: String,
@@ -105,6 +112,8 @@ pub struct Subsystem {
     pub home: String,
     #[serde(alias = "Status")]
     pub status: String,
+    #[serde(alias = "HasInit", default)]
+    pub has_init: bool,
 }
 
 impl Subsystem {
@@ -117,29 +126,57 @@ impl Subsystem {
         }
     }
 
+    /// Lists the subsystems with their status read from podman, through its API when it's
+    /// reachable and the podman CLI otherwise. Subsystems podman can't report on keep the
+    /// status apx listed.
+    pub fn get_all_with_status(podman: &Podman) -> Result<Vec<Subsystem>> {
+        let mut all = Self::get_all()?;
+
+        for subsystem in all.iter_mut() {
+            if let Err(e) = subsystem.refresh_status(podman) {
+                debug!("Could not read the status of {} from podman: {}", subsystem.name, e);
+            }
+        }
+
+        Ok(all)
+    }
+
     pub fn create(&mut self) -> Result<()> {
+        self.create_with_progress(|_| {})
+    }
+
+    // Creates the subsystem, handing each line of apx output to `on_line`.
+    pub fn create_with_progress(&mut self, on_line: impl FnMut(&str)) -> Result<()> {
         let mut command = format!(
             "subsystems new --name {} --stack {}",
-            self.name, self.stack.name
+            shell_quote(&self.name),
+            shell_quote(&self.stack.name)
         );
 
-        if self.home.len() > 0 {
-            command.push_str(&format!("--home {}", self.home));
+        if !self.home.is_empty() {
+            command.push_str(&format!(" --home {}", shell_quote(&self.home)));
         }
 
-        let res = run_apx(&command, false);
+        if self.has_init {
+            command.push_str(" --init");
+        }
 
-        match res {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
+        run_apx_with_progress(&command, on_line)?;
+
+        //Pull the status and internal name apx assigned.
+        if let Some(e) = Self::get_all()?.into_iter().find(|e| e.name == self.name) {
+            *self = e;
         }
+
+        Ok(())
     }
 
     pub fn update(&self) -> Result<()> {
         let res = run_apx(
             &format!(
                 "subsystems --name {} --stack {}",
-                self.name, self.stack.name
+                shell_quote(&self.name),
+                shell_quote(&self.stack.name)
             ),
             false,
         );
@@ -151,11 +188,10 @@ impl Subsystem {
     }
 
     pub fn remove(&self, force: bool) -> Result<()> {
-        let mut command = format!("subsystems rm --name {}", self.name);
-
-        if force {
-            command.push_str(" --force");
-        }
+        let command = with_force(
+            format!("subsystems rm --name {}", shell_quote(&self.name)),
+            force,
+        );
 
         let res = run_apx(&command, false);
 
@@ -169,8 +205,24 @@ impl Subsystem {
         self.status.contains("Up") || self.status.contains("running")
     }
 
+    /// The subsystem's container state, read from podman rather than through apx.
+    pub fn container_state(&self, podman: &Podman) -> Result<ContainerState> {
+        podman.state(&self.internal_name)
+    }
+
+    /// Re-reads the status from the subsystem's container, e.g. `running` or `exited`.
+    pub fn refresh_status(&mut self, podman: &Podman) -> Result<()> {
+        self.status = self.container_state(podman)?.status;
+        Ok(())
+    }
+
+    /// The subsystem's resource usage. It has to be running.
+    pub fn container_stats(&self, podman: &Podman) -> Result<ContainerStats> {
+        podman.stats(&self.internal_name)
+    }
+
     pub fn start(&self) -> Result<()> {
-        let res = run_apx(&format!("{} start", self.name), false);
+        let res = run_apx(&format!("{} start", shell_quote(&self.name)), false);
 
         match res {
             Ok(_) => Ok(()),
@@ -179,7 +231,7 @@ impl Subsystem {
     }
 
     pub fn stop(&self) -> Result<()> {
-        let res = run_apx(&format!("{} stop", self.name), false);
+        let res = run_apx(&format!("{} stop", shell_quote(&self.name)), false);
 
         match res {
             Ok(_) => Ok(()),
@@ -188,11 +240,10 @@ impl Subsystem {
     }
 
     pub fn reset(&self, force: bool) -> Result<()> {
-        let mut command = format!("subsystems reset --name {}", self.name);
-
-        if force {
-            command.push_str(" --force");
-        }
+        let command = with_force(
+            format!("subsystems reset --name {}", shell_quote(&self.name)),
+            force,
+        );
 
         let res = run_apx(&command, false);
 
@@ -202,8 +253,87 @@ impl Subsystem {
         }
     }
 
+    // Resolves the package manager this subsystem's stack uses.
+    pub fn package_manager(&self) -> Result<PackageManager> {
+        PackageManager::get(&self.stack.package_manager)
+    }
+
+    // Runs a package manager command inside the subsystem, through the privilege helper
+    // when the package manager needs rights, and through apx otherwise.
+    // `assume_yes` skips the package manager's own confirmation, which the caller already got.
+    fn run_pkg_command(&self, command: &str, args: &str, assume_yes: bool) -> Result<String> {
+        let line = format!("{command} {args}").trim_end().to_string();
+        let yes = match assume_yes {
+            true => " -y",
+            false => "",
+        };
+
+        let elevated = match self.package_manager() {
+            Ok(pm) if pm.needs_elevation() => pm.command_line(command).map(|c| {
+                let pm_yes = match (assume_yes, pm.assume_yes_flag()) {
+                    (true, Some(flag)) => format!(" {flag}"),
+                    _ => String::new(),
+                };
+
+                format!("{c}{pm_yes} {args}").trim_end().to_string()
+            }),
+            Ok(_) => None,
+            Err(e) => {
+                debug!("Could not resolve package manager for {}: {}", self.name, e);
+                None
+            }
+        };
+
+        run_apx_privileged(
+            format!("{} {command}{yes} {args}", shell_quote(&self.name)).trim_end(),
+            &self.name,
+            elevated.as_deref(),
+            &format!(
+                "Administrator rights are needed to run \"{line}\" in the {} subsystem",
+                self.name
+            ),
+        )
+    }
+
+    // Searches the subsystem's package manager, returning the matching package names.
+    pub fn search(&self, query: &str) -> Result<Vec<String>> {
+        let output = run_apx(
+            &format!("{} search {}", shell_quote(&self.name), shell_quote(query)),
+            false,
+        )?;
+
+        Ok(parse_package_names(&output))
+    }
+
+    // Lists the packages installed in the subsystem.
+    pub fn list_installed(&self) -> Result<Vec<Package>> {
+        let output = run_apx(&format!("{} list", shell_quote(&self.name)), false)?;
+
+        Ok(parse_installed(&output))
+    }
+
+    // Returns the package manager's details for a package.
+    pub fn show(&self, package: &str) -> Result<String> {
+        run_apx(&format!("{} show {}", shell_quote(&self.name), shell_quote(package)), false)
+    }
+
+    pub fn install(&self, packages: &[String]) -> Result<()> {
+        self.run_pkg_command("install", &quote_all(packages), true)?;
+        Ok(())
+    }
+
+    pub fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_pkg_command("remove", &quote_all(packages), true)?;
+        Ok(())
+    }
+
+    pub fn purge(&self, packages: &[String]) -> Result<()> {
+        self.run_pkg_command("purge", &quote_all(packages), true)?;
+        Ok(())
+    }
+
     pub fn autoremove(&self) -> Result<()> {
-        let res = run_apx(&format!("{} autoremove", self.name), false);
+        let res = self.run_pkg_command("autoremove", "", false);
 
         match res {
             Ok(_) => Ok(()),
@@ -212,16 +342,172 @@ impl Subsystem {
     }
 
     pub fn clean(&self) -> Result<()> {
-        let res = run_apx(&format!("{} clean", self.name), false);
+        let res = self.run_pkg_command("clean", "", false);
 
         match res {
             Ok(_) => Ok(()),
             Err(e) => Err(e),
         }
     }
+
+    // Lists the applications inside the subsystem that ship a desktop entry.
+    pub fn list_apps(&self) -> Result<Vec<DesktopApp>> {
+        let script = "grep -H -E '^(Name|Exec|NoDisplay)=' /usr/share/applications/*.desktop 2>/dev/null || true";
+        let output = run_apx(
+            &format!("{} run -- sh -c {}", shell_quote(&self.name), shell_quote(script)),
+            false,
+        )?;
+
+        let exported = exported_desktop_files(&self.export_prefixes());
+
+        let mut apps = parse_desktop_entries(&output);
+        for app in apps.iter_mut() {
+            app.exported = exported.iter().any(|f| f.ends_with(&format!("-{}.desktop", app.id)));
+        }
+
+        Ok(apps)
+    }
+
+    // The file name prefixes apx gives the desktop entries it exports from this subsystem.
+    fn export_prefixes(&self) -> Vec<String> {
+        let mut prefixes = vec![format!("apx-{}", self.name)];
+
+        if !self.internal_name.is_empty() && !prefixes.contains(&self.internal_name) {
+            prefixes.push(self.internal_name.clone());
+        }
+
+        prefixes
+    }
+
+    // Names of the applications exported from this subsystem, read from the host's
+    // desktop files so the subsystem doesn't have to be started.
+    pub fn list_exported_apps(&self) -> Vec<String> {
+        let dir = match applications_dir() {
+            Some(d) => d,
+            None => return vec![],
+        };
+
+        let mut names: Vec<String> = exported_desktop_files(&self.export_prefixes())
+            .into_iter()
+            .map(|file| {
+                std::fs::read_to_string(dir.join(&file))
+                    .ok()
+                    .and_then(|entry| {
+                        entry
+                            .lines()
+                            .find_map(|l| l.strip_prefix("Name="))
+                            .map(|n| n.trim().to_string())
+                    })
+                    .unwrap_or(file)
+            })
+            .collect();
+
+        names.sort_by_key(|n| n.to_lowercase());
+        names
+    }
+
+    // Lists the binaries exported from this subsystem to the host.
+    pub fn list_exported_binaries(&self) -> Vec<ExportedBinary> {
+        exported_binaries(&self.export_prefixes())
+    }
+
+    pub fn export_app(&self, app: &str) -> Result<()> {
+        run_apx(
+            &format!("{} export --app-name {}", shell_quote(&self.name), shell_quote(app)),
+            false,
+        )?;
+        Ok(())
+    }
+
+    pub fn unexport_app(&self, app: &str) -> Result<()> {
+        run_apx(
+            &format!("{} unexport --app-name {}", shell_quote(&self.name), shell_quote(app)),
+            false,
+        )?;
+        Ok(())
+    }
+
+    pub fn export_binary(&self, binary: &str) -> Result<()> {
+        run_apx(
+            &format!("{} export --bin {}", shell_quote(&self.name), shell_quote(binary)),
+            false,
+        )?;
+        Ok(())
+    }
+
+    pub fn unexport_binary(&self, binary: &str) -> Result<()> {
+        run_apx(
+            &format!("{} unexport --bin {}", shell_quote(&self.name), shell_quote(binary)),
+            false,
+        )?;
+        Ok(())
+    }
+
+    // Starts an application inside the subsystem, without waiting for it to exit.
+    pub fn launch_app(&self, app: &DesktopApp) -> Result<()> {
+        spawn_apx(&format!(
+            "{} run -- sh -c {}",
+            shell_quote(&self.name),
+            shell_quote(&app.command())
+        ))
+    }
+}
+
+// An application inside a subsystem, from its desktop entry.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DesktopApp {
+    /// Desktop file name without `.desktop`, as `apx export --app-name` expects.
+    pub id: String,
+    pub name: String,
+    pub exec: String,
+    /// Whether the app's desktop entry is exported to the host.
+    pub exported: bool,
+}
+
+impl DesktopApp {
+    // The Exec line with its field codes (%U, %f, ...) dropped.
+    pub fn command(&self) -> String {
+        self.exec
+            .split_whitespace()
+            .filter(|t| !(t.len() == 2 && t.starts_with('%')))
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+// A binary exported from a subsystem, as the wrapper script apx leaves on the host.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExportedBinary {
+    /// File name of the wrapper on the host.
+    pub name: String,
+    /// Path of the binary inside the subsystem, as `apx unexport --bin` expects.
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+}
+
+// apx's --force only skips its "are you sure?" prompt, which can't be answered without a
+// terminal, so callers confirm with the user first and only force when asked to.
+fn with_force(command: String, force: bool) -> String {
+    match force {
+        true => format!("{command} --force"),
+        false => command,
+    }
+}
+
+fn quote_all(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|v| shell_quote(v))
+        .collect::<Vec<String>>()
+        .join(" ")
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PackageManager {
     #[serde(alias = "Name")]
     pub name: String,
@@ -261,6 +547,55 @@ impl PackageManager {
         }
     }
 
+    pub fn get(name: &str) -> Result<PackageManager> {
+        match Self::get_all()?.into_iter().find(|p| p.name == name) {
+            Some(p) => Ok(p),
+            None => Err(ApxError::CommandError {
+                error: format!("Package manager {name} not found"),
+            }
+            .into()),
+        }
+    }
+
+    // The command line apx runs in the subsystem for one of its package manager commands,
+    // e.g. `apt install` for `install`, without the sudo apx adds for `need_sudo`.
+    pub fn command_line(&self, command: &str) -> Option<String> {
+        let cmd = match command {
+            "autoremove" => &self.cmd_auto_remove,
+            "clean" => &self.cmd_clean,
+            "install" => &self.cmd_install,
+            "list" => &self.cmd_list,
+            "purge" => &self.cmd_purge,
+            "remove" => &self.cmd_remove,
+            "search" => &self.cmd_search,
+            "show" => &self.cmd_show,
+            "update" => &self.cmd_update,
+            "upgrade" => &self.cmd_upgrade,
+            _ => return None,
+        };
+
+        match cmd.is_empty() {
+            true => None,
+            false => Some(format!("{} {cmd}", self.name)),
+        }
+    }
+
+    // The flag that skips the package manager's own confirmation, for the known ones.
+    // apx takes `-y` for this, but the package managers don't agree: pacman's `-y`
+    // refreshes the database instead, and apk doesn't ask in the first place.
+    pub fn assume_yes_flag(&self) -> Option<&'static str> {
+        match self.name.as_str() {
+            "apt" | "apt-get" | "dnf" | "yum" | "zypper" | "xbps-install" => Some("-y"),
+            "pacman" | "yay" | "paru" => Some("--noconfirm"),
+            _ => None,
+        }
+    }
+
+    // Whether commands run through this package manager need to be elevated.
+    pub fn needs_elevation(&self) -> bool {
+        crate::privilege::needs_elevation(self.need_sudo)
+    }
+
     pub fn create(&mut self) -> Result<()> {
         let command = format!(
             "pkgmanagers new --name '{}' --need-sudo '{}' 
@@ -337,14 +672,13 @@ impl PackageManager {
     }
 
     pub fn remove(&self, force: bool) -> Result<()> {
-        let mut command = format!("pkgmanagers rm --name '{}'", self.name);
+        let command = with_force(
+            format!("pkgmanagers rm --name {}", shell_quote(&self.name)),
+            force,
+        );
 
         debug!("command: {command}");
 
-        if force {
-            command.push_str(" --force");
-        }
-
         let res = run_apx(&command, false);
 
         match res {
@@ -353,3 +687,265 @@ impl PackageManager {
         }
     }
 }
+
+// Splits package manager output into candidate (name, rest) pairs.
+// Continuation lines (indented descriptions) and status lines are skipped,
+// and repository/arch suffixes such as `/stable` or `.x86_64` are dropped.
+fn package_lines(output: &str) -> Vec<(String, Vec<&str>)> {
+    let mut lines = vec![];
+
+    for line in output.lines() {
+        if line.is_empty() || line.starts_with(char::is_whitespace) || line.contains("...") {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let token = match tokens.next() {
+            Some(t) => t,
+            None => continue,
+        };
+
+        let name = token
+            .split('/')
+            .next()
+            .unwrap_or(token)
+            .trim_end_matches(".x86_64")
+            .trim_end_matches(".aarch64")
+            .trim_end_matches(".noarch");
+
+        let valid = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-._@".contains(c));
+
+        if !name.is_empty() && valid {
+            lines.push((name.to_string(), tokens.collect()));
+        }
+    }
+
+    lines
+}
+
+// Pulls package names out of package manager search output.
+fn parse_package_names(output: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+
+    for (name, _) in package_lines(output) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+// Pulls installed packages and their versions out of package manager list output.
+fn parse_installed(output: &str) -> Vec<Package> {
+    package_lines(output)
+        .into_iter()
+        .filter_map(|(name, rest)| {
+            let version = rest.first()?;
+
+            match version.chars().any(|c| c.is_ascii_digit()) {
+                true => Some(Package {
+                    name,
+                    version: version.to_string(),
+                }),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+// Pulls desktop apps out of `grep -H` output over desktop files, e.g.
+// `/usr/share/applications/foo.desktop:Name=Foo`. Only the first value of
+// each key counts, later ones belong to actions. Hidden entries are dropped.
+fn parse_desktop_entries(output: &str) -> Vec<DesktopApp> {
+    let mut apps: Vec<DesktopApp> = vec![];
+    let mut hidden: Vec<String> = vec![];
+
+    for line in output.lines() {
+        let (path, entry) = match line.split_once(".desktop:") {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let id = path.rsplit('/').next().unwrap_or(path).to_string();
+        let (key, value) = match entry.split_once('=') {
+            Some(v) => v,
+            None => continue,
+        };
+
+        if key == "NoDisplay" && value.trim() == "true" {
+            hidden.push(id.clone());
+        }
+
+        let app = match apps.iter_mut().find(|a| a.id == id) {
+            Some(a) => a,
+            None => {
+                apps.push(DesktopApp {
+                    id: id.clone(),
+                    name: String::new(),
+                    exec: String::new(),
+                    exported: false,
+                });
+                apps.last_mut().unwrap()
+            }
+        };
+
+        match key {
+            "Name" if app.name.is_empty() => app.name = value.trim().to_string(),
+            "Exec" if app.exec.is_empty() => app.exec = value.trim().to_string(),
+            _ => {}
+        }
+    }
+
+    apps.retain(|a| !a.exec.is_empty() && !hidden.contains(&a.id));
+    for app in apps.iter_mut() {
+        if app.name.is_empty() {
+            app.name = app.id.clone();
+        }
+    }
+    apps.sort_by_key(|a| a.name.to_lowercase());
+
+    apps
+}
+
+// The host's directory for user desktop files.
+fn applications_dir() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_DATA_HOME") {
+        Some(d) if !d.is_empty() => PathBuf::from(d),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+    };
+
+    Some(dir.join("applications"))
+}
+
+// Lists the host's user desktop files starting with one of `prefixes`.
+fn exported_desktop_files(prefixes: &[String]) -> Vec<String> {
+    let dir = match applications_dir() {
+        Some(d) => d,
+        None => return vec![],
+    };
+
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(e) => e,
+        Err(e) => {
+            debug!("Could not read {}: {}", dir.display(), e);
+            return vec![];
+        }
+    };
+
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|f| prefixes.iter().any(|p| f.starts_with(&format!("{p}-"))))
+        .collect()
+}
+
+// Lists the wrappers apx exported into the host's `~/.local/bin` for the containers named in `prefixes`.
+fn exported_binaries(prefixes: &[String]) -> Vec<ExportedBinary> {
+    let dir = match std::env::var_os("HOME") {
+        Some(h) => PathBuf::from(h).join(".local/bin"),
+        None => return vec![],
+    };
+
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(e) => e,
+        Err(e) => {
+            debug!("Could not read {}: {}", dir.display(), e);
+            return vec![];
+        }
+    };
+
+    let mut binaries: Vec<ExportedBinary> = entries
+        .filter_map(|e| e.ok())
+        //Wrappers are a few lines long, anything big is a real binary.
+        .filter(|e| e.metadata().map(|m| m.is_file() && m.len() < 16 * 1024).unwrap_or(false))
+        .filter_map(|e| {
+            let script = std::fs::read_to_string(e.path()).ok()?;
+            let (container, path) = parse_exported_binary(&script)?;
+
+            prefixes.contains(&container).then(|| ExportedBinary {
+                name: e.file_name().to_string_lossy().to_string(),
+                path,
+            })
+        })
+        .collect();
+
+    binaries.sort_by(|a, b| a.name.cmp(&b.name));
+    binaries
+}
+
+// Pulls the container name and binary path out of a distrobox-export wrapper, e.g.
+// `# name: apx-box` and `exec "/usr/bin/distrobox-enter" -n apx-box -- '/usr/bin/htop' "$@"`.
+fn parse_exported_binary(script: &str) -> Option<(String, String)> {
+    if !script.lines().any(|l| l.trim() == "# distrobox_binary") {
+        return None;
+    }
+
+    let container = script
+        .lines()
+        .find_map(|l| l.strip_prefix("# name:"))?
+        .trim()
+        .to_string();
+
+    let path = script
+        .lines()
+        .filter(|l| l.contains("distrobox-enter"))
+        .find_map(|l| {
+            let (_, command) = l.split_once(" -- ")?;
+            command.trim().strip_prefix('\'')?.split('\'').next()
+        })?
+        .to_string();
+
+    Some((container, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exported_binary_wrappers_are_parsed() {
+        let script = r#"#!/bin/sh
+# distrobox_binary
+# name: apx-box
+if [ -z "${CONTAINER_ID}" ]; then
+	exec "/usr/bin/distrobox-enter"  -n apx-box  --  '/usr/bin/htop'  "$@"
+elif [ -n "${CONTAINER_ID}" ] && [ "${CONTAINER_ID}" != "apx-box" ]; then
+	exec distrobox-host-exec '/home/me/.local/bin/htop' "$@"
+else
+	exec '/usr/bin/htop' "$@"
+fi
+"#;
+
+        assert_eq!(
+            parse_exported_binary(script),
+            Some(("apx-box".to_string(), "/usr/bin/htop".to_string()))
+        );
+        assert_eq!(parse_exported_binary("#!/bin/sh\nexec htop \"$@\"\n"), None);
+    }
+
+    #[test]
+    fn assume_yes_flag_follows_the_package_manager() {
+        let pkg_manager = |name: &str| PackageManager {
+            name: name.into(),
+            need_sudo: true,
+            built_in: true,
+            cmd_auto_remove: String::new(),
+            cmd_clean: String::new(),
+            cmd_install: "install".into(),
+            cmd_list: String::new(),
+            cmd_purge: String::new(),
+            cmd_remove: String::new(),
+            cmd_search: String::new(),
+            cmd_show: String::new(),
+            cmd_update: String::new(),
+            cmd_upgrade: String::new(),
+        };
+
+        assert_eq!(pkg_manager("apt").assume_yes_flag(), Some("-y"));
+        assert_eq!(pkg_manager("pacman").assume_yes_flag(), Some("--noconfirm"));
+        assert_eq!(pkg_manager("apk").assume_yes_flag(), None);
+    }
+}
@@ -6,6 +6,21 @@ pub enum ApxError {
     #[error("Command error: {error}")]
     CommandError { error: String },
 
+    #[error("Authorization denied by {helper}")]
+    AuthorizationDenied { helper: String },
+
+    #[error("Privilege helper not found: {helper}")]
+    HelperNotFound { helper: String },
+
+    #[error("No supported terminal emulator found")]
+    NoTerminal,
+
+    #[error("Terminal command template is missing {{command}}: {template}")]
+    InvalidTerminalTemplate { template: String },
+
+    #[error("Podman API error {status}: {message}")]
+    PodmanApi { status: u16, message: String },
+
     #[error("IO Error")]
     IoError(#[from] io::Error),
 }
\ No newline at end of file
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// How many invocations are kept before the oldest are dropped.
+pub const DEFAULT_CAPACITY: usize = 200;
+
+/// A single command run by apx-shim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub id: u64,
+    /// The command line as handed to `sh -c`.
+    pub command: String,
+    pub started: SystemTime,
+    /// How long the command ran, `None` while it's still running.
+    pub duration: Option<Duration>,
+    /// The exit code, `None` while running, when killed by a signal or when it failed to start.
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Invocation {
+    pub fn finished(&self) -> bool {
+        self.duration.is_some()
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// The start time as `HH:MM:SS` UTC.
+    pub fn started_clock(&self) -> String {
+        let secs = self
+            .started
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        format!(
+            "{:02}:{:02}:{:02}",
+            secs / 3600 % 24,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+
+    /// A plain text rendering of the invocation, for bug reports.
+    pub fn report(&self) -> String {
+        let exit = match (self.finished(), self.exit_code) {
+            (false, _) => "running".to_string(),
+            (true, Some(code)) => code.to_string(),
+            (true, None) => "none".to_string(),
+        };
+
+        let duration = match self.duration {
+            Some(d) => format!("{} ms", d.as_millis()),
+            None => "-".to_string(),
+        };
+
+        format!(
+            "$ {}\nstarted: {} UTC\nduration: {duration}\nexit code: {exit}\n--- stdout ---\n{}\n--- stderr ---\n{}\n",
+            self.command,
+            self.started_clock(),
+            self.stdout.trim_end(),
+            self.stderr.trim_end(),
+        )
+    }
+
+    /// Whether the command or its output contains `filter`, ignoring case.
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+
+        filter.is_empty()
+            || self.command.to_lowercase().contains(&filter)
+            || self.stdout.to_lowercase().contains(&filter)
+            || self.stderr.to_lowercase().contains(&filter)
+    }
+}
+
+struct History {
+    entries: VecDeque<Invocation>,
+    capacity: usize,
+}
+
+static HISTORY: Mutex<History> = Mutex::new(History {
+    entries: VecDeque::new(),
+    capacity: DEFAULT_CAPACITY,
+});
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static REVISION: AtomicU64 = AtomicU64::new(0);
+
+fn with_history<T>(f: impl FnOnce(&mut History) -> T) -> Option<T> {
+    match HISTORY.lock() {
+        Ok(mut h) => {
+            let res = f(&mut h);
+            REVISION.fetch_add(1, Ordering::Relaxed);
+            Some(res)
+        }
+        Err(e) => {
+            warn!("Could not access the command history: {}", e);
+            None
+        }
+    }
+}
+
+/// Sets how many invocations are kept, dropping the oldest ones if needed.
+pub fn set_capacity(capacity: usize) {
+    with_history(|h| {
+        h.capacity = capacity.max(1);
+        while h.entries.len() > h.capacity {
+            h.entries.pop_front();
+        }
+    });
+}
+
+/// A snapshot of the recorded invocations, oldest first.
+pub fn entries() -> Vec<Invocation> {
+    match HISTORY.lock() {
+        Ok(h) => h.entries.iter().cloned().collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Bumped on every change to the history, so callers can skip unchanged snapshots.
+pub fn revision() -> u64 {
+    REVISION.load(Ordering::Relaxed)
+}
+
+pub fn clear() {
+    with_history(|h| h.entries.clear());
+}
+
+// Records the start of a command, returning its id for `finish`.
+pub(crate) fn start(command: &str) -> u64 {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+
+    with_history(|h| {
+        if h.entries.len() >= h.capacity {
+            h.entries.pop_front();
+        }
+
+        h.entries.push_back(Invocation {
+            id,
+            command: command.to_string(),
+            started: SystemTime::now(),
+            duration: None,
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+        });
+    });
+
+    id
+}
+
+// Records the outcome of a command started with `start`.
+pub(crate) fn finish(id: u64, exit_code: Option<i32>, stdout: &str, stderr: &str) {
+    with_history(|h| {
+        if let Some(entry) = h.entries.iter_mut().find(|e| e.id == id) {
+            entry.duration = Some(entry.started.elapsed().unwrap_or_default());
+            entry.exit_code = exit_code;
+            entry.stdout = stdout.to_string();
+            entry.stderr = stderr.to_string();
+        }
+    });
+}
@@ -1,4 +1,13 @@
 pub mod command;
 pub mod entities;
 pub mod error;
-pub use entities::{PackageManager, Stack, Subsystem};
\ No newline at end of file
+pub mod history;
+pub mod podman;
+pub mod privilege;
+pub mod templates;
+pub mod terminal;
+
+#[cfg(test)]
+mod test_support;
+
+pub use entities::{DesktopApp, ExportedBinary, Package, PackageManager, Stack, Subsystem};
\ No newline at end of file
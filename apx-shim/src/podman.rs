@@ -0,0 +1,679 @@
+use crate::command::{host_spawn_prefix, run_command, shell_quote};
+use crate::error::ApxError;
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use tracing::{debug, warn};
+
+// Oldest libpod API version with everything used here.
+const API_PREFIX: &str = "/v4.0.0/libpod";
+
+/// The state podman reports for a container.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerState {
+    pub status: String,
+    pub running: bool,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub pid: i64,
+    #[serde(default)]
+    pub exit_code: i32,
+    #[serde(default)]
+    pub started_at: String,
+    #[serde(default)]
+    pub finished_at: String,
+}
+
+/// Resource usage of a running container, memory and IO in bytes.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ContainerStats {
+    #[serde(rename = "CPU", default)]
+    pub cpu_percent: f64,
+    #[serde(rename = "MemUsage", default)]
+    pub mem_usage: u64,
+    #[serde(rename = "MemLimit", default)]
+    pub mem_limit: u64,
+    #[serde(rename = "NetInput", default)]
+    pub net_input: u64,
+    #[serde(rename = "NetOutput", default)]
+    pub net_output: u64,
+    #[serde(rename = "BlockInput", default)]
+    pub block_input: u64,
+    #[serde(rename = "BlockOutput", default)]
+    pub block_output: u64,
+    #[serde(rename = "PIDs", default)]
+    pub pids: u64,
+}
+
+/// A container lifecycle event, e.g. `start`, `stop` or `remove`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContainerEvent {
+    /// The container's name, which is the subsystem's internal name for apx subsystems.
+    pub name: String,
+    pub action: String,
+}
+
+#[derive(Deserialize)]
+struct Inspect {
+    #[serde(rename = "State")]
+    state: ContainerState,
+}
+
+#[derive(Deserialize)]
+struct StatsReport {
+    #[serde(rename = "Error", default)]
+    error: Option<serde_json::Value>,
+    #[serde(rename = "Stats", default)]
+    stats: Vec<ContainerStats>,
+}
+
+#[derive(Deserialize, Default)]
+struct Actor {
+    #[serde(rename = "Attributes", default)]
+    attributes: HashMap<String, String>,
+}
+
+// The API sends Docker-style events, the CLI its own format, so both are accepted.
+#[derive(Deserialize)]
+struct RawEvent {
+    #[serde(rename = "Type", default)]
+    kind: String,
+    #[serde(rename = "Action", alias = "Status", default)]
+    action: String,
+    #[serde(rename = "Name", default)]
+    name: Option<String>,
+    #[serde(rename = "Actor", default)]
+    actor: Actor,
+}
+
+impl RawEvent {
+    fn into_event(self) -> Option<ContainerEvent> {
+        if !self.kind.is_empty() && self.kind != "container" {
+            return None;
+        }
+
+        let name = self
+            .name
+            .or_else(|| self.actor.attributes.get("name").cloned())?;
+
+        Some(ContainerEvent {
+            name,
+            action: self.action,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    #[serde(default)]
+    message: String,
+}
+
+// The rootless socket first, as that's where apx's containers live.
+fn default_socket() -> Option<PathBuf> {
+    let from_env = std::env::var("CONTAINER_HOST")
+        .ok()
+        .and_then(|h| h.strip_prefix("unix://").map(PathBuf::from));
+
+    let rootless = std::env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .map(|d| Path::new(&d).join("podman/podman.sock"));
+
+    [from_env, rootless, Some(PathBuf::from("/run/podman/podman.sock"))]
+        .into_iter()
+        .flatten()
+        .find(|p| p.exists())
+}
+
+/// Reads container state from podman, through its REST API when the socket is reachable
+/// and the podman CLI otherwise.
+#[derive(Clone, Debug, Default)]
+pub struct Podman {
+    socket: Option<PathBuf>,
+}
+
+impl Podman {
+    /// Uses the API if a podman socket can be found, e.g. with `podman.socket` enabled.
+    pub fn new() -> Self {
+        Self {
+            socket: default_socket(),
+        }
+    }
+
+    /// Talks to the API on `socket`.
+    pub fn with_socket(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: Some(socket.into()),
+        }
+    }
+
+    /// Only uses the podman CLI.
+    pub fn cli() -> Self {
+        Self { socket: None }
+    }
+
+    /// The API socket in use, if any.
+    pub fn socket(&self) -> Option<&Path> {
+        self.socket.as_deref()
+    }
+
+    /// The state of `container`, by name or ID.
+    pub fn state(&self, container: &str) -> Result<ContainerState> {
+        if let Some(socket) = &self.socket {
+            let path = format!("{API_PREFIX}/containers/{}/json", encode(container));
+
+            match get_json::<Inspect>(socket, &path) {
+                Ok(inspect) => return Ok(inspect.state),
+                Err(e) if is_api_error(&e) => return Err(e),
+                Err(e) => warn!("podman API failed, falling back to the CLI: {e}"),
+            }
+        }
+
+        let json = run_command(
+            format!("{} container inspect {}", podman_bin(), shell_quote(container)),
+            false,
+        )?;
+        let inspect: Vec<Inspect> = serde_json::from_str(&json)?;
+
+        match inspect.into_iter().next() {
+            Some(i) => Ok(i.state),
+            None => Err(no_such_container(container)),
+        }
+    }
+
+    /// A snapshot of `container`'s resource usage. It has to be running.
+    pub fn stats(&self, container: &str) -> Result<ContainerStats> {
+        if let Some(socket) = &self.socket {
+            let path = format!(
+                "{API_PREFIX}/containers/stats?containers={}&stream=false",
+                encode(container)
+            );
+
+            match get_json::<StatsReport>(socket, &path) {
+                Ok(report) => {
+                    if let Some(e) = report.error.filter(|e| !e.is_null()) {
+                        return Err(ApxError::PodmanApi {
+                            status: 200,
+                            message: e.to_string(),
+                        }
+                        .into());
+                    }
+
+                    return report
+                        .stats
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| no_such_container(container));
+                }
+                Err(e) if is_api_error(&e) => return Err(e),
+                Err(e) => warn!("podman API failed, falling back to the CLI: {e}"),
+            }
+        }
+
+        let json = run_command(
+            format!(
+                "{} stats --no-stream --format {} {}",
+                podman_bin(),
+                shell_quote("{{json .ContainerStats}}"),
+                shell_quote(container)
+            ),
+            false,
+        )?;
+
+        match json.lines().find(|l| !l.trim().is_empty()) {
+            Some(line) => Ok(serde_json::from_str(line)?),
+            None => Err(no_such_container(container)),
+        }
+    }
+
+    /// Container events as they happen. Blocks between events, so it belongs on its own
+    /// thread.
+    pub fn events(&self) -> Result<Events> {
+        if let Some(socket) = &self.socket {
+            let filters = encode(r#"{"type":["container"]}"#);
+            let path = format!("{API_PREFIX}/events?stream=true&filters={filters}");
+
+            match request(socket, &path) {
+                Ok(body) => return Ok(Events::Api(BufReader::new(body))),
+                Err(e) if is_api_error(&e) => return Err(e),
+                Err(e) => warn!("podman API failed, falling back to the CLI: {e}"),
+            }
+        }
+
+        // Runs until dropped, so it's kept out of the command history.
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(format!(
+                "{} events --format json --filter type=container",
+                podman_bin()
+            ))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("podman events has no output"))?;
+
+        Ok(Events::Cli(child, BufReader::new(stdout)))
+    }
+}
+
+/// A stream of container events, see [`Podman::events`].
+pub enum Events {
+    Api(BufReader<Body>),
+    Cli(Child, BufReader<ChildStdout>),
+}
+
+impl Iterator for Events {
+    type Item = Result<ContainerEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = String::new();
+            let read = match self {
+                Events::Api(reader) => reader.read_line(&mut line),
+                Events::Cli(_, reader) => reader.read_line(&mut line),
+            };
+
+            match read {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<RawEvent>(&line) {
+                Ok(raw) => {
+                    if let Some(event) = raw.into_event() {
+                        return Some(Ok(event));
+                    }
+                }
+                Err(e) => debug!("Skipping unreadable podman event: {e}"),
+            }
+        }
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        if let Events::Cli(child, _) = self {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+fn podman_bin() -> String {
+    match host_spawn_prefix() {
+        Some(prefix) => format!("{prefix} podman"),
+        None => "podman".into(),
+    }
+}
+
+// Whether the API answered with an error, which the CLI would give too, rather than not
+// being reachable.
+fn is_api_error(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<ApxError>(), Some(ApxError::PodmanApi { .. }))
+}
+
+fn no_such_container(container: &str) -> anyhow::Error {
+    ApxError::PodmanApi {
+        status: 404,
+        message: format!("no such container {container}"),
+    }
+    .into()
+}
+
+// Percent-encodes everything but unreserved characters, enough for names and filters.
+fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn get_json<T: DeserializeOwned>(socket: &Path, path: &str) -> Result<T> {
+    let mut body = String::new();
+    request(socket, path)?.read_to_string(&mut body)?;
+
+    Ok(serde_json::from_str(&body)?)
+}
+
+// Sends a GET over the socket, returning the body of a successful response.
+fn request(socket: &Path, path: &str) -> Result<Body> {
+    debug!("podman API GET {path}");
+
+    let mut stream = UnixStream::connect(socket)?;
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: d\r\nUser-Agent: apx-shim\r\nConnection: close\r\n\r\n"
+    )?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not an HTTP response"))?;
+
+    let mut chunked = false;
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            break;
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+
+            match name.trim().to_ascii_lowercase().as_str() {
+                "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                "content-length" => length = value.parse::<u64>().ok(),
+                _ => {}
+            }
+        }
+    }
+
+    let mut body = match (chunked, length) {
+        (true, _) => Body::Chunked {
+            reader,
+            remaining: 0,
+            done: false,
+        },
+        (false, Some(length)) => Body::Plain(reader.take(length)),
+        (false, None) => Body::Plain(reader.take(u64::MAX)),
+    };
+
+    if !(200..300).contains(&status) {
+        let mut text = String::new();
+        let _ = body.read_to_string(&mut text);
+
+        let message = serde_json::from_str::<ApiError>(&text)
+            .map(|e| e.message)
+            .unwrap_or(text);
+
+        return Err(ApxError::PodmanApi { status, message }.into());
+    }
+
+    Ok(body)
+}
+
+/// The body of a response from the podman API.
+pub enum Body {
+    Plain(io::Take<BufReader<UnixStream>>),
+    Chunked {
+        reader: BufReader<UnixStream>,
+        /// Bytes left in the current chunk.
+        remaining: usize,
+        done: bool,
+    },
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let (reader, remaining, done) = match self {
+            Body::Plain(reader) => return reader.read(buf),
+            Body::Chunked {
+                reader,
+                remaining,
+                done,
+            } => (reader, remaining, done),
+        };
+
+        if *done || buf.is_empty() {
+            return Ok(0);
+        }
+
+        if *remaining == 0 {
+            let mut size = String::new();
+            if reader.read_line(&mut size)? == 0 {
+                *done = true;
+                return Ok(0);
+            }
+
+            // Chunk extensions after ';' aren't used by podman.
+            let size = size.trim().split(';').next().unwrap_or_default();
+            *remaining = usize::from_str_radix(size, 16)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            if *remaining == 0 {
+                *done = true;
+                return Ok(0);
+            }
+        }
+
+        let wanted = buf.len().min(*remaining);
+        let read = reader.read(&mut buf[..wanted])?;
+        *remaining -= read;
+
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "podman closed the connection mid-chunk",
+            ));
+        } else if *remaining == 0 {
+            // Each chunk ends with CRLF.
+            let mut crlf = String::new();
+            reader.read_line(&mut crlf)?;
+        }
+
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{set_execution_strategy, ExecutionStrategy};
+    use crate::test_support::{lock_globals, ScratchDir};
+    use std::os::unix::net::UnixListener;
+
+    const STATE: &str = r#"{"Id":"abc","State":{"Status":"running","Running":true,"Paused":false,"Pid":4242,"ExitCode":0,"StartedAt":"2026-10-18T10:00:00Z","FinishedAt":"0001-01-01T00:00:00Z"}}"#;
+    const STATS: &str = r#"{"Error":null,"Stats":[{"ContainerID":"abc","Name":"apx-box","CPU":1.5,"MemUsage":1048576,"MemLimit":8388608,"NetInput":10,"NetOutput":20,"BlockInput":30,"BlockOutput":40,"PIDs":3}]}"#;
+    const NOT_FOUND: &str = r#"{"cause":"no such container","message":"no container with name or ID \"ghost\" found: no such container","response":404}"#;
+
+    fn response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    fn chunked(chunks: &[&str]) -> String {
+        let mut response =
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n"
+                .to_string();
+
+        for chunk in chunks {
+            response.push_str(&format!("{:x}\r\n{chunk}\r\n", chunk.len()));
+        }
+
+        response + "0\r\n\r\n"
+    }
+
+    // Replays recorded responses on a socket in `dir`, picked by the start of the request
+    // path. Anything else gets a 404.
+    fn serve(dir: &ScratchDir, responses: Vec<(&'static str, String)>) -> PathBuf {
+        let socket = dir.path().join("podman.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().map_while(|s| s.ok()) {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                    header.clear();
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let reply = responses
+                    .iter()
+                    .find(|(prefix, _)| path.starts_with(&format!("{API_PREFIX}{prefix}")))
+                    .map(|(_, reply)| reply.clone())
+                    .unwrap_or_else(|| response("404 Not Found", NOT_FOUND));
+
+                let _ = stream.write_all(reply.as_bytes());
+            }
+        });
+
+        socket
+    }
+
+    #[test]
+    fn state_and_stats_are_read_from_the_api() {
+        let dir = ScratchDir::new();
+        let podman = Podman::with_socket(serve(
+            &dir,
+            vec![
+                ("/containers/apx-box/json", response("200 OK", STATE)),
+                ("/containers/stats", chunked(&[&STATS[..40], &STATS[40..]])),
+            ],
+        ));
+
+        let state = podman.state("apx-box").unwrap();
+        assert!(state.running);
+        assert_eq!(state.status, "running");
+        assert_eq!(state.pid, 4242);
+
+        let stats = podman.stats("apx-box").unwrap();
+        assert_eq!(stats.cpu_percent, 1.5);
+        assert_eq!(stats.mem_usage, 1048576);
+        assert_eq!(stats.pids, 3);
+    }
+
+    #[test]
+    fn missing_containers_are_api_errors() {
+        let dir = ScratchDir::new();
+        let podman = Podman::with_socket(serve(&dir, vec![]));
+
+        match podman.state("ghost").unwrap_err().downcast::<ApxError>() {
+            Ok(ApxError::PodmanApi { status, message }) => {
+                assert_eq!(status, 404);
+                assert!(message.contains("no such container"));
+            }
+            e => panic!("unexpected error: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn events_are_read_from_a_chunked_stream() {
+        let start = r#"{"Type":"container","Action":"start","Actor":{"ID":"abc","Attributes":{"name":"apx-box","image":"fedora"}}}"#;
+        let died = r#"{"Type":"container","Action":"died","Actor":{"ID":"abc","Attributes":{"name":"apx-box"}}}"#;
+        let volume = r#"{"Type":"volume","Action":"create","Actor":{"ID":"v","Attributes":{"name":"data"}}}"#;
+
+        let dir = ScratchDir::new();
+        let podman = Podman::with_socket(serve(
+            &dir,
+            vec![(
+                "/events",
+                chunked(&[
+                    &format!("{start}\n"),
+                    &format!("{volume}\n"),
+                    //An event split over two chunks.
+                    &died[..30],
+                    &format!("{}\n", &died[30..]),
+                ]),
+            )],
+        ));
+
+        let events: Vec<ContainerEvent> = podman.events().unwrap().map(|e| e.unwrap()).collect();
+
+        assert_eq!(
+            events,
+            vec![
+                ContainerEvent {
+                    name: "apx-box".into(),
+                    action: "start".into(),
+                },
+                ContainerEvent {
+                    name: "apx-box".into(),
+                    action: "died".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_chunks_are_errors() {
+        let dir = ScratchDir::new();
+        let truncated = format!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}",
+            STATE.len(),
+            &STATE[..20]
+        );
+        let podman = Podman::with_socket(serve(
+            &dir,
+            vec![("/containers/apx-box/json", truncated.clone()), ("/events", truncated)],
+        ));
+
+        let path = format!("{API_PREFIX}/containers/apx-box/json");
+        assert!(get_json::<Inspect>(podman.socket().unwrap(), &path).is_err());
+
+        let mut events = podman.events().unwrap();
+        assert!(matches!(events.next(), Some(Err(_))));
+    }
+
+    #[test]
+    fn cli_is_used_when_the_socket_is_missing() {
+        let _guard = lock_globals();
+        let dir = ScratchDir::new();
+
+        dir.script(
+            "podman",
+            r#"case "$1" in
+container) echo '[{"State":{"Status":"exited","Running":false,"ExitCode":1}}]' ;;
+stats) echo '{"CPU":2.5,"MemUsage":2048,"PIDs":1}' ;;
+*) exit 125 ;;
+esac"#,
+        );
+
+        set_execution_strategy(ExecutionStrategy::Direct);
+        let path = std::env::var("PATH").unwrap_or_default();
+        std::env::set_var("PATH", format!("{}:{path}", dir.path().display()));
+
+        let podman = Podman::with_socket(dir.path().join("missing.sock"));
+        let state = podman.state("apx-box");
+        let stats = podman.stats("apx-box");
+
+        std::env::set_var("PATH", path);
+
+        let state = state.unwrap();
+        assert!(!state.running);
+        assert_eq!(state.status, "exited");
+        assert_eq!(state.exit_code, 1);
+
+        let stats = stats.unwrap();
+        assert_eq!(stats.cpu_percent, 2.5);
+        assert_eq!(stats.mem_usage, 2048);
+    }
+}
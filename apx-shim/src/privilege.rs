@@ -0,0 +1,111 @@
+use crate::command::shell_quote;
+use crate::error::ApxError;
+use anyhow::Result;
+use std::os::unix::fs::MetadataExt;
+use std::sync::RwLock;
+use tracing::{debug, warn};
+
+/// Default helper used to elevate package manager commands inside a subsystem.
+///
+/// It runs in the container, the same way apx runs `sudo` for package managers that
+/// need it, and asks through the desktop's polkit agent. pkexec shows polkit's own
+/// message rather than the prompt; helpers that take `{prompt}` show ours.
+pub const DEFAULT_HELPER: &str = "pkexec";
+
+/// Environment variable that overrides the elevation helper.
+pub const HELPER_ENV: &str = "APX_SHIM_PRIVILEGE_HELPER";
+
+// Exit code of a dismissed authorization (pkexec).
+const EXIT_NOT_AUTHORIZED: i32 = 126;
+
+static HELPER: RwLock<Option<String>> = RwLock::new(None);
+
+/// Overrides the helper used to elevate commands, e.g. `pkexec` or `sudo -A -p {prompt}`
+/// with an askpass program the subsystem can reach.
+///
+/// `{prompt}` is replaced with the (quoted) prompt text, if present.
+/// Passing `None` restores the environment/default helper.
+pub fn set_helper(helper: Option<String>) {
+    match HELPER.write() {
+        Ok(mut h) => *h = helper,
+        Err(e) => warn!("Could not set privilege helper: {}", e),
+    }
+}
+
+/// The helper command template currently in use.
+pub fn helper() -> String {
+    if let Ok(h) = HELPER.read() {
+        if let Some(h) = h.as_ref() {
+            return h.clone();
+        }
+    }
+
+    match std::env::var(HELPER_ENV) {
+        Ok(h) if !h.trim().is_empty() => h,
+        _ => DEFAULT_HELPER.into(),
+    }
+}
+
+// Finds out if the current process already has root rights.
+pub fn is_root() -> bool {
+    match std::fs::metadata("/proc/self") {
+        Ok(m) => m.uid() == 0,
+        Err(_) => false,
+    }
+}
+
+/// Whether a command needs to be elevated, given the package manager's `need_sudo` flag.
+pub fn needs_elevation(need_sudo: bool) -> bool {
+    need_sudo && !is_root()
+}
+
+// Whether stderr is the helper refusing the user, as opposed to the elevated command failing.
+// pkexec exits with 127 both for this and when it can't run the command, so only its
+// message tells them apart.
+fn is_refusal(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+
+    [
+        "not authorized",
+        "incorrect password attempt",
+        "a password is required",
+        "no askpass program specified",
+        "is not in the sudoers file",
+        "is not allowed to execute",
+    ]
+    .iter()
+    .any(|p| stderr.contains(p))
+}
+
+/// The program the helper runs, e.g. `pkexec`, to look it up before relying on it.
+pub fn helper_program(helper: &str) -> &str {
+    helper.split_whitespace().next().unwrap_or(helper)
+}
+
+/// Builds the helper prefix for a command, substituting `{prompt}`.
+///
+/// The helper isn't looked up here: it runs inside the subsystem, so it has to be
+/// looked up there.
+pub fn helper_command(prompt: &str) -> Result<String> {
+    let template = helper();
+
+    if template.trim().is_empty() {
+        return Err(ApxError::HelperNotFound { helper: template }.into());
+    }
+
+    debug!("elevating through: {}", template);
+
+    Ok(template.replace("{prompt}", &shell_quote(prompt)))
+}
+
+/// Maps a failed elevated command to the error the user should see, if it failed
+/// on the helper rather than on the command it ran.
+pub fn elevation_error(code: Option<i32>, stderr: &str, helper: &str) -> Option<ApxError> {
+    let program = helper_program(helper).to_string();
+
+    match code {
+        Some(EXIT_NOT_AUTHORIZED) => Some(ApxError::AuthorizationDenied { helper: program }),
+        _ if is_refusal(stderr) => Some(ApxError::AuthorizationDenied { helper: program }),
+        _ => None,
+    }
+}
@@ -0,0 +1,213 @@
+use crate::entities::Stack;
+
+/// A distribution a template can be built on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemplateBase {
+    pub name: &'static str,
+    pub image: &'static str,
+    /// One of apx's built-in package managers.
+    pub package_manager: &'static str,
+}
+
+pub const UBUNTU: TemplateBase = TemplateBase {
+    name: "Ubuntu 24.04",
+    image: "docker.io/library/ubuntu:24.04",
+    package_manager: "apt",
+};
+
+pub const FEDORA: TemplateBase = TemplateBase {
+    name: "Fedora 41",
+    image: "registry.fedoraproject.org/fedora:41",
+    package_manager: "dnf",
+};
+
+pub const ARCH: TemplateBase = TemplateBase {
+    name: "Arch Linux",
+    image: "docker.io/library/archlinux:latest",
+    package_manager: "pacman",
+};
+
+/// A template's packages on one base, as the base names them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemplateVariant {
+    pub base: TemplateBase,
+    pub packages: &'static [&'static str],
+}
+
+/// A ready-made stack for a common development setup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackTemplate {
+    /// Used as the suggested stack name.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub variants: &'static [TemplateVariant],
+}
+
+impl StackTemplate {
+    /// A stack for `variant`, ready to be tweaked and created.
+    pub fn to_stack(&self, variant: &TemplateVariant) -> Stack {
+        Stack {
+            name: format!("{}-{}", self.id, variant.base.package_manager),
+            base: variant.base.image.to_string(),
+            packages: variant.packages.iter().map(|p| p.to_string()).collect(),
+            package_manager: variant.base.package_manager.to_string(),
+            built_in: false,
+        }
+    }
+}
+
+/// Templates shipped with apx-shim, available offline.
+pub const TEMPLATES: &[StackTemplate] = &[
+    StackTemplate {
+        id: "rust",
+        name: "Rust",
+        description: "rustup with a C toolchain and the libraries most crates link against.",
+        variants: &[
+            TemplateVariant {
+                base: UBUNTU,
+                packages: &["build-essential", "pkg-config", "libssl-dev", "rustup", "git"],
+            },
+            TemplateVariant {
+                base: FEDORA,
+                packages: &["gcc", "pkgconf-pkg-config", "openssl-devel", "rustup", "git"],
+            },
+            TemplateVariant {
+                base: ARCH,
+                packages: &["base-devel", "pkgconf", "openssl", "rustup", "git"],
+            },
+        ],
+    },
+    StackTemplate {
+        id: "python-data",
+        name: "Python data science",
+        description: "Python with NumPy, pandas, SciPy, Matplotlib and Jupyter.",
+        variants: &[
+            TemplateVariant {
+                base: UBUNTU,
+                packages: &[
+                    "python3",
+                    "python3-pip",
+                    "python3-venv",
+                    "python3-numpy",
+                    "python3-pandas",
+                    "python3-scipy",
+                    "python3-matplotlib",
+                    "jupyter-notebook",
+                ],
+            },
+            TemplateVariant {
+                base: FEDORA,
+                packages: &[
+                    "python3",
+                    "python3-pip",
+                    "python3-numpy",
+                    "python3-pandas",
+                    "python3-scipy",
+                    "python3-matplotlib",
+                    "python3-notebook",
+                ],
+            },
+            TemplateVariant {
+                base: ARCH,
+                packages: &[
+                    "python",
+                    "python-pip",
+                    "python-numpy",
+                    "python-pandas",
+                    "python-scipy",
+                    "python-matplotlib",
+                    "jupyter-notebook",
+                ],
+            },
+        ],
+    },
+    StackTemplate {
+        id: "node",
+        name: "Node.js",
+        description: "Node.js and npm, with a compiler for native modules.",
+        variants: &[
+            TemplateVariant {
+                base: UBUNTU,
+                packages: &["nodejs", "npm", "build-essential", "git"],
+            },
+            TemplateVariant {
+                base: FEDORA,
+                packages: &["nodejs", "npm", "gcc-c++", "make", "git"],
+            },
+            TemplateVariant {
+                base: ARCH,
+                packages: &["nodejs", "npm", "base-devel", "git"],
+            },
+        ],
+    },
+    StackTemplate {
+        id: "cpp",
+        name: "C/C++",
+        description: "GCC and Clang with CMake, Meson, Ninja, GDB and ccache.",
+        variants: &[
+            TemplateVariant {
+                base: UBUNTU,
+                packages: &[
+                    "build-essential",
+                    "clang",
+                    "clang-format",
+                    "cmake",
+                    "meson",
+                    "ninja-build",
+                    "pkg-config",
+                    "gdb",
+                    "ccache",
+                ],
+            },
+            TemplateVariant {
+                base: FEDORA,
+                packages: &[
+                    "gcc",
+                    "gcc-c++",
+                    "make",
+                    "clang",
+                    "clang-tools-extra",
+                    "cmake",
+                    "meson",
+                    "ninja-build",
+                    "pkgconf-pkg-config",
+                    "gdb",
+                    "ccache",
+                ],
+            },
+            TemplateVariant {
+                base: ARCH,
+                packages: &[
+                    "base-devel",
+                    "clang",
+                    "cmake",
+                    "meson",
+                    "ninja",
+                    "pkgconf",
+                    "gdb",
+                    "ccache",
+                ],
+            },
+        ],
+    },
+    StackTemplate {
+        id: "android",
+        name: "Android tools",
+        description: "A JDK with adb, fastboot and Gradle for building and flashing devices.",
+        variants: &[
+            TemplateVariant {
+                base: UBUNTU,
+                packages: &["openjdk-17-jdk", "adb", "fastboot", "gradle", "unzip", "git"],
+            },
+            TemplateVariant {
+                base: FEDORA,
+                packages: &["java-17-openjdk-devel", "android-tools", "gradle", "unzip", "git"],
+            },
+            TemplateVariant {
+                base: ARCH,
+                packages: &["jdk17-openjdk", "android-tools", "gradle", "unzip", "git"],
+            },
+        ],
+    },
+];
@@ -0,0 +1,132 @@
+use crate::command::{get_apx_bin, host_spawn_prefix, run_recorded, shell_quote};
+use crate::error::ApxError;
+use anyhow::Result;
+use std::process::{Command, Stdio};
+use tracing::debug;
+use which::which;
+
+/// Placeholder replaced with the command to run inside the terminal.
+pub const COMMAND_PLACEHOLDER: &str = "{command}";
+
+/// A terminal emulator we know how to hand a command to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Terminal {
+    pub name: &'static str,
+    pub binary: &'static str,
+    /// Arguments that go before the command, e.g. `-e` or `--`.
+    pub exec_args: &'static str,
+}
+
+impl Terminal {
+    /// The command template for this terminal, with the `{command}` placeholder.
+    pub fn template(&self) -> String {
+        match self.exec_args.is_empty() {
+            true => format!("{} {COMMAND_PLACEHOLDER}", self.binary),
+            false => format!("{} {} {COMMAND_PLACEHOLDER}", self.binary, self.exec_args),
+        }
+    }
+}
+
+/// Terminals in order of preference, COSMIC Terminal first.
+pub const KNOWN_TERMINALS: &[Terminal] = &[
+    Terminal { name: "COSMIC Terminal", binary: "cosmic-term", exec_args: "-e" },
+    Terminal { name: "GNOME Console", binary: "kgx", exec_args: "--" },
+    Terminal { name: "Ptyxis", binary: "ptyxis", exec_args: "--" },
+    Terminal { name: "GNOME Terminal", binary: "gnome-terminal", exec_args: "--" },
+    Terminal { name: "Konsole", binary: "konsole", exec_args: "-e" },
+    Terminal { name: "Tilix", binary: "tilix", exec_args: "-e" },
+    Terminal { name: "Xfce Terminal", binary: "xfce4-terminal", exec_args: "-x" },
+    Terminal { name: "Alacritty", binary: "alacritty", exec_args: "-e" },
+    Terminal { name: "kitty", binary: "kitty", exec_args: "" },
+    Terminal { name: "foot", binary: "foot", exec_args: "" },
+    Terminal { name: "WezTerm", binary: "wezterm", exec_args: "start --" },
+    Terminal { name: "xterm", binary: "xterm", exec_args: "-e" },
+];
+
+// Finds out if a binary is available on the host, looking through the sandbox if needed.
+fn host_has_binary(binary: &str) -> bool {
+    match host_spawn_prefix() {
+        Some(prefix) => Command::new("sh")
+            .arg("-c")
+            .arg(format!("{prefix} sh -c {}", shell_quote(&format!("command -v {binary}"))))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false),
+        None => which(binary).is_ok(),
+    }
+}
+
+/// The known terminals installed on the host.
+pub fn detect() -> Vec<Terminal> {
+    KNOWN_TERMINALS
+        .iter()
+        .filter(|t| host_has_binary(t.binary))
+        .copied()
+        .collect()
+}
+
+/// Fills a terminal template with the command to run in it.
+///
+/// The template must contain `{command}`, e.g. `foot {command}`.
+pub fn fill_template(template: &str, command: &str) -> Result<String> {
+    if !template.contains(COMMAND_PLACEHOLDER) {
+        return Err(ApxError::InvalidTerminalTemplate {
+            template: template.into(),
+        }
+        .into());
+    }
+
+    Ok(template.replace(COMMAND_PLACEHOLDER, command))
+}
+
+// The command that enters a subsystem, as run on the host.
+fn enter_command(subsystem: &str) -> String {
+    //The terminal runs on the host, so apx is resolved there too.
+    let apx = match host_spawn_prefix() {
+        Some(_) => "apx".to_string(),
+        None => get_apx_bin(),
+    };
+
+    format!("{apx} {} enter", shell_quote(subsystem))
+}
+
+/// Opens a terminal inside a subsystem with `apx <name> enter`.
+///
+/// `template` is a custom terminal command (see [`fill_template`]); when empty,
+/// the first detected terminal is used.
+pub fn open_subsystem(subsystem: &str, template: Option<&str>) -> Result<()> {
+    let template = match template.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(t) => t.to_string(),
+        None => match detect().first() {
+            Some(t) => t.template(),
+            None => return Err(ApxError::NoTerminal.into()),
+        },
+    };
+
+    let mut command = fill_template(&template, &enter_command(subsystem))?;
+
+    if let Some(prefix) = host_spawn_prefix() {
+        command = format!("{prefix} {command}");
+    }
+
+    debug!("opening terminal: {}", command);
+
+    // Background the terminal so we don't wait on it, leaving it to be reaped by init.
+    let status = run_recorded(
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("{command} >/dev/null 2>&1 &")),
+    )?
+    .status;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ApxError::CommandError {
+            error: format!("Could not start {command}"),
+        }
+        .into())
+    }
+}
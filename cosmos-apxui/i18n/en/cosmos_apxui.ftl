@@ -10,3 +10,6 @@ subsystems = Subsystems
 subsystem = Subsystem
 pkgmanagers = Package Managers
 pkgmanager = Package Manager
+packages = Packages
+command-log = Command log
+settings = Settings
@@ -2,7 +2,11 @@
 
 use crate::config::Config;
 use crate::fl;
-use crate::pages::{pkgmanagers, stacks, subsystems, Page, PageModel};
+use crate::launch::{Flags, Launch};
+use crate::log::{LogDrawer, LogMessage};
+use crate::notifications;
+use crate::settings::{SettingsMessage, SettingsPage};
+use crate::pages::{self, packages, pkgmanagers, stacks, subsystems, Page, PageModel};
 use cosmic::{
     app::{context_drawer, Core, Task},
     cosmic_config::{self, CosmicConfigEntry},
@@ -20,6 +24,7 @@ use cosmic::{
 };
 use futures_util::SinkExt;
 use std::collections::HashMap;
+use std::time::Duration;
 use tracing::error;
 
 const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
@@ -38,8 +43,13 @@ pub struct AppModel {
     key_binds: HashMap<menu::KeyBind, MenuAction>,
     // Configuration data that persists between application runs.
     config: Config,
+    /// Handle used to write settings changes back, if the config could be opened.
+    config_handler: Option<cosmic_config::Config>,
+    settings: SettingsPage,
 
     page_models: HashMap<Page, Box<dyn PageModel>>,
+    /// History of apx commands, shown in the context drawer.
+    log: LogDrawer,
 }
 
 /// Messages emitted by the application and its widgets.
@@ -52,6 +62,15 @@ pub enum Message {
     LaunchUrl(String),
     Navigate(Entity),
     SubNavigate(Entity),
+    Reload(Page),
+    OpenTerminal(String),
+    AutoRefresh,
+    NotificationSent,
+    /// The user picked an action on one of our notifications.
+    NotificationAction(String),
+    Settings(SettingsMessage),
+    Log(LogMessage),
+    Packages(packages::PackagesMessage),
     PkgManager(pkgmanagers::PkgManagerMessage),
     Stack(stacks::StackMessage),
     Subsystem(subsystems::SubsystemMessage),
@@ -63,7 +82,7 @@ impl Application for AppModel {
     type Executor = cosmic::executor::Default;
 
     /// Data that your application receives to its init method.
-    type Flags = ();
+    type Flags = Flags;
 
     /// Messages which the application and its widgets will emit.
     type Message = Message;
@@ -80,7 +99,7 @@ impl Application for AppModel {
     }
 
     /// Initializes the application with any given flags and startup commands.
-    fn init(core: Core, _flags: Self::Flags) -> (Self, Task<Self::Message>) {
+    fn init(core: Core, flags: Self::Flags) -> (Self, Task<Self::Message>) {
         // Create a nav bar with three page items.
         let mut nav = nav_bar::Model::default();
 
@@ -90,6 +109,11 @@ impl Application for AppModel {
             .icon(icon::from_name("utilities-terminal-symbolic"))
             .activate();
 
+        nav.insert()
+            // .text(fl!("packages"))
+            .data::<Page>(Page::Packages)
+            .icon(icon::from_name("package-x-generic-symbolic"));
+
         nav.insert()
             // .text(fl!("pkgmanagers"))
             .data::<Page>(Page::PkgManagers)
@@ -105,15 +129,39 @@ impl Application for AppModel {
             Page::Subsystems,
             Box::new(subsystems::SubSystemsModel::new()),
         );
+        page_models.insert(Page::Packages, Box::new(packages::PackagesModel::new()));
         page_models.insert(
             Page::PkgManagers,
             Box::new(pkgmanagers::PkgManagerModel::new()),
         );
         page_models.insert(Page::Stacks, Box::new(stacks::StacksModel::new()));
 
-        page_models
-            .iter_mut()
-            .for_each(|(_, model)| model.update_items());
+        let config_handler = cosmic_config::Config::new(Self::APP_ID, Config::VERSION).ok();
+        // Optional configuration file for an application.
+        let config = config_handler
+            .as_ref()
+            .map(|context| match Config::get_entry(context) {
+                Ok(config) => config,
+                Err((errors, config)) => {
+                    for why in errors {
+                        tracing::error!(%why, "error loading app config");
+                    }
+
+                    config
+                }
+            })
+            .unwrap_or_default();
+
+        apx_shim::command::set_execution_strategy(config.execution_strategy);
+
+        let mut tasks: Vec<_> = page_models
+            .values_mut()
+            .map(|model| {
+                // Nothing is loaded yet, so the reload this may ask for is covered below.
+                let _ = model.apply_config(&config);
+                model.update_items()
+            })
+            .collect();
 
         // Construct the app model with the runtime's core.
         let mut app = AppModel {
@@ -121,26 +169,21 @@ impl Application for AppModel {
             context_page: ContextPage::default(),
             nav,
             page_models,
+            log: LogDrawer::default(),
             key_binds: HashMap::new(),
-            // Optional configuration file for an application.
-            config: cosmic_config::Config::new(Self::APP_ID, Config::VERSION)
-                .map(|context| match Config::get_entry(&context) {
-                    Ok(config) => config,
-                    Err((errors, config)) => {
-                        for why in errors {
-                            tracing::error!(%why, "error loading app config");
-                        }
-
-                        config
-                    }
-                })
-                .unwrap_or_default(),
+            config,
+            config_handler,
+            settings: SettingsPage::new(),
         };
 
+        if let Some(launch) = flags.launch {
+            tasks.push(app.launch(launch));
+        }
+
         // Create a startup command that sets the window title.
-        let command = app.update_title();
+        tasks.push(app.update_title());
 
-        (app, command)
+        (app, Task::batch(tasks))
     }
 
     /// Elements to pack at the start of the header bar.
@@ -149,13 +192,40 @@ impl Application for AppModel {
             menu::root(fl!("view")),
             menu::items(
                 &self.key_binds,
-                vec![menu::Item::Button(fl!("about"), None, MenuAction::About)],
+                vec![
+                    menu::Item::Button(fl!("settings"), None, MenuAction::Settings),
+                    menu::Item::Button(fl!("command-log"), None, MenuAction::Log),
+                    menu::Item::Button(fl!("about"), None, MenuAction::About),
+                ],
             ),
         )]);
 
         vec![menu_bar.into()]
     }
 
+    /// Elements to pack at the end of the header bar.
+    fn header_end(&self) -> Vec<Element<Self::Message>> {
+        match self.active_page() {
+            Page::Subsystems => vec![widget::button::icon(icon::from_name("list-add-symbolic"))
+                .on_press(subsystems::SubsystemMessage::New.into())
+                .into()],
+            Page::Stacks => vec![
+                widget::button::icon(icon::from_name("folder-templates-symbolic"))
+                    .on_press(stacks::StackMessage::Templates.into())
+                    .into(),
+                widget::button::icon(icon::from_name("list-add-symbolic"))
+                    .on_press(stacks::StackMessage::New.into())
+                    .into(),
+            ],
+            _ => vec![],
+        }
+    }
+
+    /// Displays a dialog requested by the active page, if any.
+    fn dialog(&self) -> Option<Element<Self::Message>> {
+        self.page_models.get(&self.active_page())?.dialog()
+    }
+
     /// Enables the COSMIC application to create a nav bar with this model.
     fn nav_model(&self) -> Option<&nav_bar::Model> {
         None //Some(&self.nav)
@@ -173,6 +243,16 @@ impl Application for AppModel {
                 Message::ToggleContextPage(ContextPage::About),
             )
             .title(fl!("about")),
+            ContextPage::Log => context_drawer::context_drawer(
+                self.log.view(),
+                Message::ToggleContextPage(ContextPage::Log),
+            )
+            .title(fl!("command-log")),
+            ContextPage::Settings => context_drawer::context_drawer(
+                self.settings.view(&self.config),
+                Message::ToggleContextPage(ContextPage::Settings),
+            )
+            .title(fl!("settings")),
         })
     }
 
@@ -195,28 +275,34 @@ impl Application for AppModel {
             }
         };
 
+        let mut items = cosmic::iced_widget::column![
+            HorizontalSegmentedButton::new(&self.nav)
+                .button_height(32)
+                .button_padding([8, 16, 8, 16])
+                .button_spacing(8)
+                .minimum_button_width(32)
+                .width(Length::Fill)
+                .button_alignment(Alignment::Center)
+                .on_activate(|id| Message::Navigate(id))
+                .style(theme::SegmentedButton::TabBar),
+            VerticalSegmentedButton::new(page_model.current_items())
+                .style(theme::SegmentedButton::TabBar)
+                .button_height(32)
+                .button_padding([8, 16, 8, 16])
+                .button_spacing(8)
+                .width(Length::Fill)
+                .on_activate(|id| Message::SubNavigate(id))
+        ];
+
+        if let Some(state) = pages::load_state_view(page_model.load_state(), Message::Reload(*page)) {
+            items = items.push(widget::container(state).padding(16));
+        }
+
         cosmic::iced_widget::row![
-            widget::Container::new(cosmic::iced_widget::column![
-                HorizontalSegmentedButton::new(&self.nav)
-                    .button_height(32)
-                    .button_padding([8, 16, 8, 16])
-                    .button_spacing(8)
-                    .minimum_button_width(32)
-                    .width(Length::Fill)
-                    .button_alignment(Alignment::Center)
-                    .on_activate(|id| Message::Navigate(id))
-                    .style(theme::SegmentedButton::TabBar),
-                VerticalSegmentedButton::new(page_model.current_items())
-                    .style(theme::SegmentedButton::TabBar)
-                    .button_height(32)
-                    .button_padding([8, 16, 8, 16])
-                    .button_spacing(8)
-                    .width(Length::Fill)
-                    .on_activate(|id| Message::SubNavigate(id))
-            ])
-            .width(Length::Fixed(300.))
-            .style(|_| theme::Container::primary(&cosmic_theme::Theme::default()))
-            .height(Length::Fill),
+            widget::Container::new(items)
+                .width(Length::Fixed(300.))
+                .style(|_| theme::Container::primary(&cosmic_theme::Theme::default()))
+                .height(Length::Fill),
             widget::Container::new(
                 page_model
                     .view()
@@ -240,8 +326,23 @@ impl Application for AppModel {
     /// beginning of the application, and persist through its lifetime.
     fn subscription(&self) -> Subscription<Self::Message> {
         struct MySubscription;
+        struct NotificationActions;
+
+        // Keep the command log current while it's open.
+        let log_ticks = match self.core.window.show_context && self.context_page == ContextPage::Log {
+            true => cosmic::iced::time::every(Duration::from_secs(1)).map(|_| LogMessage::Tick.into()),
+            false => Subscription::none(),
+        };
+
+        let auto_refresh = match self.config.auto_refresh_secs {
+            0 => Subscription::none(),
+            secs => cosmic::iced::time::every(Duration::from_secs(secs.into()))
+                .map(|_| Message::AutoRefresh),
+        };
 
         Subscription::batch(vec![
+            log_ticks,
+            auto_refresh,
             // Create a subscription which emits updates through a channel.
             Subscription::run_with_id(
                 std::any::TypeId::of::<MySubscription>(),
@@ -251,6 +352,10 @@ impl Application for AppModel {
                     futures_util::future::pending().await
                 }),
             ),
+            Subscription::run_with_id(
+                std::any::TypeId::of::<NotificationActions>(),
+                notifications::actions(),
+            ),
             // Watch for application configuration changes.
             self.core()
                 .watch_config::<Config>(Self::APP_ID)
@@ -287,10 +392,45 @@ impl Application for AppModel {
                     self.context_page = context_page;
                     self.core.window.show_context = true;
                 }
+
+                if context_page == ContextPage::Log {
+                    self.log.refresh();
+                }
+
+                if context_page == ContextPage::Settings && self.core.window.show_context {
+                    return self.settings.load();
+                }
             }
 
             Message::UpdateConfig(config) => {
                 self.config = config;
+                return self.apply_config();
+            }
+
+            Message::Settings(message) => {
+                if self.settings.update(message, &mut self.config) {
+                    if let Some(handler) = &self.config_handler {
+                        if let Err(why) = self.config.write_entry(handler) {
+                            tracing::error!(?why, "error saving app config");
+                        }
+                    }
+
+                    return self.apply_config();
+                }
+            }
+
+            Message::AutoRefresh => {
+                if let Some(model) = self.page_models.get_mut(&Page::Subsystems) {
+                    return model.update_items();
+                }
+            }
+
+            Message::NotificationSent => {}
+
+            Message::NotificationAction(key) => {
+                if let Some(name) = notifications::focus_target(&key) {
+                    return self.launch(Launch::Subsystem(name.to_string()));
+                }
             }
 
             Message::LaunchUrl(url) => match open::that_detached(&url) {
@@ -313,27 +453,72 @@ impl Application for AppModel {
                     }
                 };
 
-                page_model.on_select(entity);
+                return page_model.on_select(entity);
+            }
+            Message::OpenTerminal(name) => {
+                let template = self.config.terminal_command.clone();
+
+                return Task::perform(
+                    pages::blocking(move || {
+                        apx_shim::terminal::open_subsystem(&name, Some(&template))
+                    }),
+                    |res| Message::Subsystem(subsystems::SubsystemMessage::TerminalOpened(res)).into(),
+                );
+            }
+            Message::Log(message) => return self.log.update(message),
+            Message::Reload(page) => {
+                if let Some(model) = self.page_models.get_mut(&page) {
+                    return model.update_items();
+                }
+            }
+            Message::Packages(_) => {
+                return self
+                    .page_models
+                    .get_mut(&Page::Packages)
+                    .unwrap()
+                    .on_message(message)
+            }
+            Message::PkgManager(_) => {
+                return self
+                    .page_models
+                    .get_mut(&Page::PkgManagers)
+                    .unwrap()
+                    .on_message(message)
+            }
+            Message::Stack(_) => {
+                return self
+                    .page_models
+                    .get_mut(&Page::Stacks)
+                    .unwrap()
+                    .on_message(message)
+            }
+            Message::Subsystem(_) => {
+                return self
+                    .page_models
+                    .get_mut(&Page::Subsystems)
+                    .unwrap()
+                    .on_message(message)
             }
-            Message::PkgManager(_) => self
-                .page_models
-                .get_mut(&Page::PkgManagers)
-                .unwrap()
-                .on_message(message),
-            Message::Stack(_) => self
-                .page_models
-                .get_mut(&Page::Stacks)
-                .unwrap()
-                .on_message(message),
-            Message::Subsystem(_) => self
-                .page_models
-                .get_mut(&Page::Subsystems)
-                .unwrap()
-                .on_message(message),
         }
         Task::none()
     }
 
+    /// Called when the app is launched again while already running.
+    fn dbus_activation(&mut self, msg: cosmic::app::DbusActivationMessage) -> Task<Self::Message> {
+        match msg.msg {
+            cosmic::app::DbusActivationDetails::ActivateAction { action, .. } => {
+                match action.parse::<Launch>() {
+                    Ok(launch) => self.launch(launch),
+                    Err(e) => {
+                        error!("ignoring activation: {e}");
+                        Task::none()
+                    }
+                }
+            }
+            _ => self.raise(),
+        }
+    }
+
     /// Called when a nav item is selected.
     fn on_nav_select(&mut self, id: nav_bar::Id) -> Task<Self::Message> {
         // Activate the page in the model.
@@ -344,6 +529,70 @@ impl Application for AppModel {
 }
 
 impl AppModel {
+    /// Hands the current settings to apx-shim and the pages.
+    fn apply_config(&mut self) -> Task<Message> {
+        apx_shim::command::set_execution_strategy(self.config.execution_strategy);
+
+        Task::batch(
+            self.page_models
+                .values_mut()
+                .map(|model| model.apply_config(&self.config))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Switches to `page`'s tab.
+    fn show_page(&mut self, page: Page) {
+        if let Some(entity) = self.nav.iter().find(|e| self.nav.data::<Page>(*e) == Some(&page)) {
+            self.nav.activate(entity);
+        }
+    }
+
+    /// Brings the main window to the front.
+    fn raise(&self) -> Task<Message> {
+        match self.core.main_window_id() {
+            Some(id) => cosmic::iced::window::gain_focus(id),
+            None => Task::none(),
+        }
+    }
+
+    /// Shows what a launch asks for, whether from the command line, a second launch or a
+    /// notification.
+    fn launch(&mut self, launch: Launch) -> Task<Message> {
+        let (page, message): (Page, Message) = match launch {
+            Launch::Subsystem(name) => (
+                Page::Subsystems,
+                subsystems::SubsystemMessage::Focus(name).into(),
+            ),
+            Launch::NewSubsystem { stack } => (
+                Page::Subsystems,
+                subsystems::SubsystemMessage::NewWithStack(stack).into(),
+            ),
+            Launch::Stack(name) => (Page::Stacks, stacks::StackMessage::Focus(name).into()),
+            Launch::PkgManager(name) => (
+                Page::PkgManagers,
+                pkgmanagers::PkgManagerMessage::Focus(name).into(),
+            ),
+        };
+
+        self.show_page(page);
+
+        let focus = match self.page_models.get_mut(&page) {
+            Some(model) => model.on_message(message),
+            None => Task::none(),
+        };
+
+        Task::batch([focus, self.raise(), self.update_title()])
+    }
+
+    /// The page whose tab is active.
+    fn active_page(&self) -> Page {
+        match self.nav.data::<Page>(self.nav.active()) {
+            Some(page) => *page,
+            None => Page::Subsystems,
+        }
+    }
+
     /// The about page for this app.
     pub fn about(&self) -> Element<Message> {
         let cosmic_theme::Spacing { space_xxs, .. } = theme::active().cosmic().spacing;
@@ -400,11 +649,15 @@ impl AppModel {
 pub enum ContextPage {
     #[default]
     About,
+    Log,
+    Settings,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MenuAction {
     About,
+    Log,
+    Settings,
 }
 
 impl menu::action::MenuAction for MenuAction {
@@ -413,6 +666,8 @@ impl menu::action::MenuAction for MenuAction {
     fn message(&self) -> Self::Message {
         match self {
             MenuAction::About => Message::ToggleContextPage(ContextPage::About),
+            MenuAction::Log => Message::ToggleContextPage(ContextPage::Log),
+            MenuAction::Settings => Message::ToggleContextPage(ContextPage::Settings),
         }
     }
 }
@@ -1,9 +1,36 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use apx_shim::command::ExecutionStrategy;
 use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
 
-#[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
+#[derive(Debug, Clone, CosmicConfigEntry, Eq, PartialEq)]
 #[version = 1]
 pub struct Config {
-    demo: String,
+    /// Stack preselected when creating a subsystem. Empty picks the first one.
+    pub default_stack: String,
+    /// Command used to open a terminal in a subsystem, with `{command}` standing in for
+    /// `apx <name> enter`. Empty picks the first installed terminal, COSMIC Terminal first.
+    pub terminal_command: String,
+    /// Seconds between subsystem list refreshes, 0 turns refreshing off.
+    pub auto_refresh_secs: u32,
+    /// Ask before resetting or deleting anything.
+    pub confirm_destructive: bool,
+    pub show_built_in_stacks: bool,
+    pub show_built_in_pkg_managers: bool,
+    /// How apx is reached, overriding sandbox detection.
+    pub execution_strategy: ExecutionStrategy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_stack: String::new(),
+            terminal_command: String::new(),
+            auto_refresh_secs: 0,
+            confirm_destructive: true,
+            show_built_in_stacks: true,
+            show_built_in_pkg_managers: true,
+            execution_strategy: ExecutionStrategy::Auto,
+        }
+    }
 }
@@ -0,0 +1,147 @@
+use crate::app::Message;
+use cosmic::{
+    cosmic_theme::Spacing,
+    widget::{self, button, text_input, toggler},
+    Element,
+};
+
+/// Asks before a destructive action, explaining what it affects.
+pub struct ConfirmDialog {
+    title: String,
+    action: &'static str,
+    /// What's being acted on, typed back by the user for irreversible actions.
+    name: String,
+    description: String,
+    /// Things affected besides the target itself, `None` while still being worked out.
+    impact: Option<Vec<String>>,
+    impact_label: &'static str,
+    require_typed_name: bool,
+    typed: String,
+    force: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ConfirmMessage {
+    /// What else is affected by acting on the named target.
+    ImpactLoaded(String, Result<Vec<String>, String>),
+    Typed(String),
+    ForceToggled(bool),
+    Confirm,
+    Cancel,
+}
+
+impl ConfirmDialog {
+    pub fn new(
+        title: impl Into<String>,
+        action: &'static str,
+        name: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            action,
+            name: name.into(),
+            description: description.into(),
+            impact: Some(vec![]),
+            impact_label: "",
+            require_typed_name: false,
+            typed: String::new(),
+            force: false,
+        }
+    }
+
+    /// Lists what else is affected, under `label`, once an `ImpactLoaded` arrives.
+    pub fn with_impact(mut self, label: &'static str) -> Self {
+        self.impact = None;
+        self.impact_label = label;
+        self
+    }
+
+    /// Makes the user type the name before confirming.
+    pub fn irreversible(mut self) -> Self {
+        self.require_typed_name = true;
+        self
+    }
+
+    /// Whether the user asked for `--force`.
+    pub fn force(&self) -> bool {
+        self.force
+    }
+
+    fn can_confirm(&self) -> bool {
+        self.impact.is_some() && (!self.require_typed_name || self.typed.trim() == self.name)
+    }
+
+    /// Applies a message, returning true once the action is confirmed.
+    pub fn update(&mut self, message: ConfirmMessage) -> bool {
+        match message {
+            //Left over from a dialog for something else.
+            ConfirmMessage::ImpactLoaded(target, _) if target != self.name => {}
+            ConfirmMessage::ImpactLoaded(_, res) => {
+                self.impact = Some(match res {
+                    Ok(impact) => impact,
+                    Err(e) => vec![format!("Could not work out what else is affected: {e}")],
+                })
+            }
+            ConfirmMessage::Typed(text) => self.typed = text,
+            ConfirmMessage::ForceToggled(v) => self.force = v,
+            ConfirmMessage::Confirm => return self.can_confirm(),
+            //Handled by the owning page.
+            ConfirmMessage::Cancel => {}
+        }
+
+        false
+    }
+
+    pub fn view(&self, wrap: fn(ConfirmMessage) -> Message) -> Element<'_, Message> {
+        let spacing = Spacing::default();
+
+        let mut control = widget::column()
+            .spacing(spacing.space_s)
+            .push(widget::text::body(self.description.as_str()));
+
+        if !self.impact_label.is_empty() {
+            control = match &self.impact {
+                None => control.push(widget::text::caption("Checking what else is affected…")),
+                Some(impact) if impact.is_empty() => control,
+                Some(impact) => control
+                    .push(widget::text::heading(self.impact_label))
+                    .push(widget::text::body(impact.join(", "))),
+            };
+        }
+
+        if self.require_typed_name {
+            control = control
+                .push(widget::text::caption(format!(
+                    "This can't be undone. Type {} to confirm.",
+                    self.name
+                )))
+                .push(
+                    text_input(self.name.as_str(), &self.typed)
+                        .on_input(move |t| wrap(ConfirmMessage::Typed(t))),
+                );
+        }
+
+        control = control
+            .push(
+                toggler(self.force)
+                    .label("Force")
+                    .on_toggle(move |v| wrap(ConfirmMessage::ForceToggled(v))),
+            )
+            .push(widget::text::caption(
+                "Passes --force to apx, skipping its own checks.",
+            ));
+
+        let mut confirm = button::destructive(self.action);
+        if self.can_confirm() {
+            confirm = confirm.on_press(wrap(ConfirmMessage::Confirm));
+        }
+
+        widget::dialog()
+            .title(self.title.as_str())
+            .control(control)
+            .primary_action(confirm)
+            .secondary_action(button::standard("Cancel").on_press(wrap(ConfirmMessage::Cancel)))
+            .into()
+    }
+}
@@ -0,0 +1,417 @@
+use crate::app::Message;
+use crate::pages::{blocking, subsystems::SubsystemMessage};
+use apx_shim::{Stack, Subsystem};
+use cosmic::{
+    app::Task,
+    cosmic_theme::Spacing,
+    iced::Length,
+    iced_widget,
+    widget::{self, button, dropdown, text_input, toggler},
+    Element,
+};
+use cosmos_common::labelled_info;
+use futures_util::SinkExt;
+
+// Keep the progress log from growing without bound on chatty image pulls.
+const MAX_PROGRESS_LINES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Stack,
+    Name,
+    Options,
+    Creating,
+}
+
+pub struct CreateSubsystemWizard {
+    step: Step,
+    stacks: Option<Vec<Stack>>,
+    stack_names: Vec<String>,
+    selected_stack: Option<usize>,
+    name: String,
+    existing_names: Vec<String>,
+    default_stack: String,
+    custom_home: bool,
+    home: String,
+    init: bool,
+    progress: Vec<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum WizardMessage {
+    StacksLoaded(Result<Vec<Stack>, String>),
+    StackSelected(usize),
+    NameEdited(String),
+    CustomHomeToggled(bool),
+    HomeEdited(String),
+    InitToggled(bool),
+    Next,
+    Back,
+    Cancel,
+    Progress(String),
+    Finished(Result<Subsystem, String>),
+}
+
+impl From<WizardMessage> for Message {
+    fn from(message: WizardMessage) -> Self {
+        Message::Subsystem(SubsystemMessage::Wizard(message))
+    }
+}
+
+/// Checks a subsystem name against podman's container naming rules and the existing subsystems.
+pub fn validate_name(name: &str, existing: &[String]) -> Result<(), String> {
+    let first = match name.chars().next() {
+        Some(c) => c,
+        None => return Err("A name is required".into()),
+    };
+
+    if !first.is_ascii_alphanumeric() {
+        return Err("Names must start with a letter or a digit".into());
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return Err("Only letters, digits, '-', '_' and '.' are allowed".into());
+    }
+
+    if name.len() > 63 {
+        return Err("Names can be at most 63 characters long".into());
+    }
+
+    if existing.iter().any(|e| e == name) {
+        return Err(format!("A subsystem named {name} already exists"));
+    }
+
+    Ok(())
+}
+
+impl CreateSubsystemWizard {
+    pub fn new(existing_names: Vec<String>, default_stack: &str) -> Self {
+        Self {
+            step: Step::Stack,
+            stacks: None,
+            stack_names: vec![],
+            selected_stack: None,
+            name: String::new(),
+            existing_names,
+            default_stack: default_stack.to_string(),
+            custom_home: false,
+            home: String::new(),
+            init: false,
+            progress: vec![],
+            error: None,
+        }
+    }
+
+    /// Opens the wizard and starts loading the available stacks, preselecting `default_stack`.
+    pub fn open(existing_names: Vec<String>, default_stack: &str) -> (Self, Task<Message>) {
+        let task = Task::perform(blocking(Stack::get_all), |res| {
+            Message::from(WizardMessage::StacksLoaded(res)).into()
+        });
+
+        (Self::new(existing_names, default_stack), task)
+    }
+
+    fn selected(&self) -> Option<&Stack> {
+        match (&self.stacks, self.selected_stack) {
+            (Some(stacks), Some(i)) => stacks.get(i),
+            _ => None,
+        }
+    }
+
+    fn home_error(&self) -> Option<&'static str> {
+        if !self.custom_home {
+            None
+        } else if self.home.trim().is_empty() {
+            Some("A home directory is required")
+        } else if !self.home.starts_with('/') {
+            Some("The home directory must be an absolute path")
+        } else {
+            None
+        }
+    }
+
+    fn can_continue(&self) -> bool {
+        match self.step {
+            Step::Stack => self.selected().is_some(),
+            Step::Name => validate_name(&self.name, &self.existing_names).is_ok(),
+            Step::Options => self.home_error().is_none(),
+            Step::Creating => false,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn update(&mut self, message: WizardMessage) -> Task<Message> {
+        match message {
+            WizardMessage::StacksLoaded(res) => match res {
+                Ok(stacks) => {
+                    self.stack_names = stacks.iter().map(|s| s.name.clone()).collect();
+                    self.selected_stack = match stacks.iter().position(|s| s.name == self.default_stack) {
+                        Some(i) => Some(i),
+                        None if stacks.is_empty() => None,
+                        None => Some(0),
+                    };
+                    self.stacks = Some(stacks);
+                }
+                Err(e) => {
+                    self.stacks = Some(vec![]);
+                    self.error = Some(format!("Could not load stacks: {e}"));
+                }
+            },
+            WizardMessage::StackSelected(i) => self.selected_stack = Some(i),
+            WizardMessage::NameEdited(name) => self.name = name,
+            WizardMessage::CustomHomeToggled(v) => self.custom_home = v,
+            WizardMessage::HomeEdited(home) => self.home = home,
+            WizardMessage::InitToggled(v) => self.init = v,
+            WizardMessage::Back => {
+                self.error = None;
+                self.step = match self.step {
+                    Step::Stack | Step::Name => Step::Stack,
+                    Step::Options => Step::Name,
+                    Step::Creating => Step::Options,
+                }
+            }
+            WizardMessage::Next => {
+                if !self.can_continue() {
+                    return Task::none();
+                }
+
+                match self.step {
+                    Step::Stack => self.step = Step::Name,
+                    Step::Name => self.step = Step::Options,
+                    Step::Options => return self.create(),
+                    Step::Creating => {}
+                }
+            }
+            WizardMessage::Progress(line) => {
+                self.progress.push(line);
+                if self.progress.len() > MAX_PROGRESS_LINES {
+                    self.progress.remove(0);
+                }
+            }
+            WizardMessage::Finished(res) => {
+                if let Err(e) = res {
+                    self.error = Some(e);
+                }
+            }
+            //Handled by the owning page.
+            WizardMessage::Cancel => {}
+        }
+
+        Task::none()
+    }
+
+    fn create(&mut self) -> Task<Message> {
+        let stack = match self.selected() {
+            Some(s) => s.clone(),
+            None => return Task::none(),
+        };
+
+        let subsystem = Subsystem {
+            internal_name: String::new(),
+            name: self.name.clone(),
+            stack,
+            home: match self.custom_home {
+                true => self.home.trim().to_string(),
+                false => String::new(),
+            },
+            status: String::new(),
+            has_init: self.init,
+        };
+
+        self.step = Step::Creating;
+        self.progress.clear();
+        self.error = None;
+
+        let stream = cosmic::iced::stream::channel(16, move |mut output| async move {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+            let handle = tokio::task::spawn_blocking(move || {
+                let mut subsystem = subsystem;
+                subsystem
+                    .create_with_progress(|line| {
+                        let _ = tx.send(line.to_string());
+                    })
+                    .map(|_| subsystem)
+                    .map_err(|e| e.to_string())
+            });
+
+            while let Some(line) = rx.recv().await {
+                let _ = output.send(WizardMessage::Progress(line)).await;
+            }
+
+            let res = match handle.await {
+                Ok(res) => res,
+                Err(e) => Err(e.to_string()),
+            };
+
+            let _ = output.send(WizardMessage::Finished(res)).await;
+        });
+
+        Task::run(stream, |m| Message::from(m).into())
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let spacing = Spacing::default();
+
+        let (title, control): (&str, Element<'_, Message>) = match self.step {
+            Step::Stack => {
+                let mut column = widget::column().spacing(spacing.space_s);
+
+                match &self.stacks {
+                    None => column = column.push(widget::text::body("Loading stacks…")),
+                    Some(_) => {
+                        column = column.push(dropdown(
+                            &self.stack_names,
+                            self.selected_stack,
+                            |i| WizardMessage::StackSelected(i).into(),
+                        ));
+
+                        if let Some(stack) = self.selected() {
+                            let packages = match stack.packages.is_empty() {
+                                true => "None".to_string(),
+                                false => stack.packages.join(", "),
+                            };
+
+                            column = column
+                                .push(labelled_info("Base", &stack.base))
+                                .push(labelled_info("Package Manager", &stack.package_manager))
+                                .push(labelled_info("Packages", packages));
+                        }
+                    }
+                }
+
+                ("Choose a stack", column.into())
+            }
+            Step::Name => {
+                let mut column = widget::column().spacing(spacing.space_s).push(
+                    text_input("my-subsystem", &self.name)
+                        .label("Name")
+                        .on_input(|t| WizardMessage::NameEdited(t).into()),
+                );
+
+                if !self.name.is_empty() {
+                    if let Err(e) = validate_name(&self.name, &self.existing_names) {
+                        column = column.push(widget::text::caption(e));
+                    }
+                }
+
+                ("Name the subsystem", column.into())
+            }
+            Step::Options => {
+                let mut column = widget::column().spacing(spacing.space_s).push(
+                    toggler(self.custom_home)
+                        .label("Use a custom home directory")
+                        .on_toggle(|v| WizardMessage::CustomHomeToggled(v).into()),
+                );
+
+                if self.custom_home {
+                    column = column.push(
+                        text_input("/home/user/subsystems/name", &self.home)
+                            .label("Home directory")
+                            .on_input(|t| WizardMessage::HomeEdited(t).into()),
+                    );
+
+                    if let Some(e) = self.home_error() {
+                        column = column.push(widget::text::caption(e));
+                    }
+                }
+
+                column = column
+                    .push(
+                        toggler(self.init)
+                            .label("Run systemd as init")
+                            .on_toggle(|v| WizardMessage::InitToggled(v).into()),
+                    )
+                    .push(widget::text::caption(
+                        "Needed for services inside the subsystem, at the cost of a heavier container.",
+                    ));
+
+                ("Options", column.into())
+            }
+            Step::Creating => {
+                let status = match &self.error {
+                    Some(_) => format!("Failed to create {}", self.name),
+                    None => format!("Creating {}…", self.name),
+                };
+
+                let log = widget::Column::with_children(
+                    self.progress
+                        .iter()
+                        .map(|l| widget::text::caption(l.as_str()).into()),
+                );
+
+                let mut column = widget::column()
+                    .spacing(spacing.space_s)
+                    .push(widget::text::heading(status))
+                    .push(
+                        iced_widget::scrollable(log)
+                            .anchor_bottom()
+                            .height(Length::Fixed(200.))
+                            .width(Length::Fill),
+                    );
+
+                if let Some(e) = &self.error {
+                    column = column.push(widget::text::caption(e.as_str()));
+                }
+
+                ("Creating subsystem", column.into())
+            }
+        };
+
+        let mut control = widget::column().spacing(spacing.space_s).push(control);
+
+        if self.step != Step::Creating {
+            if let Some(e) = &self.error {
+                control = control.push(widget::text::caption(e.as_str()));
+            }
+        }
+
+        let mut dialog = widget::dialog().title(title).control(control);
+
+        match self.step {
+            Step::Creating => {
+                if self.error.is_some() {
+                    dialog = dialog
+                        .primary_action(
+                            button::standard("Back").on_press(WizardMessage::Back.into()),
+                        )
+                        .secondary_action(
+                            button::standard("Close").on_press(WizardMessage::Cancel.into()),
+                        );
+                }
+            }
+            step => {
+                let label = match step {
+                    Step::Options => "Create",
+                    _ => "Next",
+                };
+
+                let mut next = button::suggested(label);
+                if self.can_continue() {
+                    next = next.on_press(WizardMessage::Next.into());
+                }
+
+                dialog = dialog
+                    .primary_action(next)
+                    .secondary_action(
+                        button::standard("Cancel").on_press(WizardMessage::Cancel.into()),
+                    );
+
+                if step != Step::Stack {
+                    dialog = dialog.tertiary_action(
+                        button::text("Back").on_press(WizardMessage::Back.into()),
+                    );
+                }
+            }
+        }
+
+        dialog.into()
+    }
+}
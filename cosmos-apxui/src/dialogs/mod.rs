@@ -0,0 +1,3 @@
+pub(crate) mod confirm;
+pub(crate) mod create_subsystem;
+pub(crate) mod stack_templates;
@@ -0,0 +1,142 @@
+use crate::app::Message;
+use crate::pages::stacks::StackMessage;
+use apx_shim::templates::{StackTemplate, TemplateVariant, TEMPLATES};
+use apx_shim::Stack;
+use cosmic::{
+    cosmic_theme::Spacing,
+    iced::Length,
+    iced_widget, theme,
+    widget::{self, button, dropdown},
+    Element,
+};
+use cosmos_common::labelled_info;
+
+/// Picks one of the bundled stack templates and a base to build it on.
+pub struct TemplateGallery {
+    selected: usize,
+    variant: usize,
+    base_names: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum GalleryMessage {
+    TemplateSelected(usize),
+    BaseSelected(usize),
+    Use,
+    Cancel,
+}
+
+impl From<GalleryMessage> for Message {
+    fn from(message: GalleryMessage) -> Self {
+        Message::Stack(StackMessage::Gallery(message))
+    }
+}
+
+impl TemplateGallery {
+    pub fn new() -> Self {
+        let mut gallery = Self {
+            selected: 0,
+            variant: 0,
+            base_names: vec![],
+        };
+        gallery.select(0);
+        gallery
+    }
+
+    fn template(&self) -> &'static StackTemplate {
+        &TEMPLATES[self.selected]
+    }
+
+    fn variant(&self) -> &'static TemplateVariant {
+        &self.template().variants[self.variant]
+    }
+
+    fn select(&mut self, index: usize) {
+        if index >= TEMPLATES.len() {
+            return;
+        }
+
+        // Stay on the same base when the new template has it.
+        let base = self.base_names.get(self.variant).cloned();
+
+        self.selected = index;
+        self.base_names = self
+            .template()
+            .variants
+            .iter()
+            .map(|v| v.base.name.to_string())
+            .collect();
+        self.variant = base
+            .and_then(|b| self.base_names.iter().position(|n| *n == b))
+            .unwrap_or(0);
+    }
+
+    /// The stack to start editing from.
+    pub fn stack(&self) -> Stack {
+        self.template().to_stack(self.variant())
+    }
+
+    /// Applies a message. `Use` and `Cancel` are left to the stacks page.
+    pub fn update(&mut self, message: GalleryMessage) {
+        match message {
+            GalleryMessage::TemplateSelected(i) => self.select(i),
+            GalleryMessage::BaseSelected(i) => {
+                if i < self.base_names.len() {
+                    self.variant = i;
+                }
+            }
+            GalleryMessage::Use | GalleryMessage::Cancel => {}
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let spacing = Spacing::default();
+
+        let templates = widget::Column::with_children(TEMPLATES.iter().enumerate().map(|(i, t)| {
+            let content = widget::column()
+                .push(widget::text::heading(t.name))
+                .push(widget::text::caption(t.description));
+
+            button::custom(content)
+                .width(Length::Fill)
+                .class(match i == self.selected {
+                    true => theme::Button::Suggested,
+                    false => theme::Button::Text,
+                })
+                .on_press(GalleryMessage::TemplateSelected(i).into())
+                .into()
+        }))
+        .spacing(spacing.space_xxs);
+
+        let variant = self.variant();
+
+        let preview = widget::column()
+            .spacing(spacing.space_xxs)
+            .push(
+                widget::column()
+                    .spacing(spacing.space_xxs)
+                    .push(widget::text::caption("Base"))
+                    .push(dropdown(&self.base_names, Some(self.variant), |i| {
+                        GalleryMessage::BaseSelected(i).into()
+                    })),
+            )
+            .push(labelled_info("Image", variant.base.image))
+            .push(labelled_info("Package Manager", variant.base.package_manager))
+            .push(labelled_info("Packages", variant.packages.join(", ")));
+
+        let control = widget::column()
+            .spacing(spacing.space_s)
+            .push(iced_widget::scrollable(templates).height(Length::Fixed(240.)))
+            .push(preview)
+            .push(widget::text::caption(
+                "The stack opens in the editor, so it can be changed before it's saved.",
+            ));
+
+        widget::dialog()
+            .title("Start from a template")
+            .control(control)
+            .primary_action(button::suggested("Use template").on_press(GalleryMessage::Use.into()))
+            .secondary_action(button::standard("Cancel").on_press(GalleryMessage::Cancel.into()))
+            .into()
+    }
+}
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use std::fmt;
+use std::str::FromStr;
+
+use cosmic::app::CosmicFlags;
+
+pub const HELP_FLAGS: [&str; 2] = ["-h", "--help"];
+
+pub const USAGE: &str = "\
+Usage: cosmos-apxui [OPTIONS]
+
+Options:
+  --subsystem NAME          Show the subsystem NAME
+  --stack NAME              Show the stack NAME
+  --pkg-manager NAME        Show the package manager NAME
+  --new-subsystem           Start creating a subsystem, using the stack given by --stack
+  -h, --help                Print this help
+
+If cosmos-apxui is already running, the request is passed on to it.";
+
+/// What to show once the app is up, from the command line or a second launch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Launch {
+    Subsystem(String),
+    Stack(String),
+    PkgManager(String),
+    NewSubsystem { stack: Option<String> },
+}
+
+/// Parses the arguments after the program name. `--help` is left to the caller.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Launch>, String> {
+    let mut args = args.into_iter();
+    let mut subsystem = None;
+    let mut stack = None;
+    let mut pkg_manager = None;
+    let mut new_subsystem = false;
+
+    while let Some(arg) = args.next() {
+        // Both "--flag value" and "--flag=value" are accepted.
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+
+        let mut value = |flag: &str| {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| format!("{flag} needs a name"))
+        };
+
+        match flag.as_str() {
+            "--subsystem" => subsystem = Some(value(&flag)?),
+            "--stack" => stack = Some(value(&flag)?),
+            "--pkg-manager" => pkg_manager = Some(value(&flag)?),
+            "--new-subsystem" => new_subsystem = true,
+            _ => return Err(format!("Unknown argument {flag}\n\n{USAGE}")),
+        }
+    }
+
+    let launch = match (new_subsystem, subsystem, stack, pkg_manager) {
+        (true, None, stack, None) => Launch::NewSubsystem { stack },
+        (false, Some(name), None, None) => Launch::Subsystem(name),
+        (false, None, Some(name), None) => Launch::Stack(name),
+        (false, None, None, Some(name)) => Launch::PkgManager(name),
+        (false, None, None, None) => return Ok(None),
+        _ => return Err(format!("Only one thing can be shown at a time\n\n{USAGE}")),
+    };
+
+    Ok(Some(launch))
+}
+
+// Launches are sent to the running instance as an action name, so they round-trip
+// through a string.
+impl fmt::Display for Launch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Launch::Subsystem(name) => write!(f, "subsystem:{name}"),
+            Launch::Stack(name) => write!(f, "stack:{name}"),
+            Launch::PkgManager(name) => write!(f, "pkg-manager:{name}"),
+            Launch::NewSubsystem { stack: None } => write!(f, "new-subsystem:"),
+            Launch::NewSubsystem { stack: Some(stack) } => write!(f, "new-subsystem:{stack}"),
+        }
+    }
+}
+
+impl FromStr for Launch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, name) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Not a launch request: {s}"))?;
+        let name = name.to_string();
+
+        match kind {
+            "new-subsystem" if name.is_empty() => Ok(Launch::NewSubsystem { stack: None }),
+            "new-subsystem" => Ok(Launch::NewSubsystem { stack: Some(name) }),
+            _ if name.is_empty() => Err(format!("No name given in {s}")),
+            "subsystem" => Ok(Launch::Subsystem(name)),
+            "stack" => Ok(Launch::Stack(name)),
+            "pkg-manager" => Ok(Launch::PkgManager(name)),
+            _ => Err(format!("Not a launch request: {s}")),
+        }
+    }
+}
+
+/// Flags the app is started with.
+#[derive(Debug, Clone, Default)]
+pub struct Flags {
+    pub launch: Option<Launch>,
+}
+
+impl CosmicFlags for Flags {
+    type SubCommand = Launch;
+    type Args = Vec<String>;
+
+    fn action(&self) -> Option<&Launch> {
+        self.launch.as_ref()
+    }
+}
@@ -0,0 +1,172 @@
+use crate::app::Message;
+use apx_shim::history::{self, Invocation};
+use cosmic::{
+    app::Task,
+    cosmic_theme::Spacing,
+    iced::{Alignment, Length},
+    widget::{self, button, text_input, toggler},
+    Element,
+};
+
+/// Browses the commands apx-shim has run, with their full output.
+#[derive(Default)]
+pub struct LogDrawer {
+    entries: Vec<Invocation>,
+    revision: Option<u64>,
+    filter: String,
+    failures_only: bool,
+    expanded: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub enum LogMessage {
+    Tick,
+    FilterEdited(String),
+    FailuresOnlyToggled(bool),
+    Expand(u64),
+    Copy(u64),
+    CopyAll,
+    Clear,
+}
+
+impl From<LogMessage> for Message {
+    fn from(message: LogMessage) -> Self {
+        Message::Log(message)
+    }
+}
+
+impl LogDrawer {
+    /// Re-reads the history if it changed since the last snapshot.
+    pub fn refresh(&mut self) {
+        let revision = history::revision();
+
+        if self.revision != Some(revision) {
+            self.entries = history::entries();
+            self.revision = Some(revision);
+        }
+    }
+
+    fn visible(&self) -> impl Iterator<Item = &Invocation> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|e| !self.failures_only || (e.finished() && !e.succeeded()))
+            .filter(|e| e.matches(&self.filter))
+    }
+
+    pub fn update(&mut self, message: LogMessage) -> Task<Message> {
+        match message {
+            LogMessage::Tick => self.refresh(),
+            LogMessage::FilterEdited(filter) => self.filter = filter,
+            LogMessage::FailuresOnlyToggled(v) => self.failures_only = v,
+            LogMessage::Expand(id) => {
+                self.expanded = match self.expanded == Some(id) {
+                    true => None,
+                    false => Some(id),
+                }
+            }
+            LogMessage::Copy(id) => {
+                if let Some(entry) = self.entries.iter().find(|e| e.id == id) {
+                    return cosmic::iced::clipboard::write(entry.report());
+                }
+            }
+            LogMessage::CopyAll => {
+                let report = self
+                    .visible()
+                    .map(|e| e.report())
+                    .collect::<Vec<String>>()
+                    .join("\n");
+
+                return cosmic::iced::clipboard::write(report);
+            }
+            LogMessage::Clear => {
+                history::clear();
+                self.expanded = None;
+                self.refresh();
+            }
+        }
+
+        Task::none()
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let spacing = Spacing::default();
+
+        let mut list = widget::Column::new().spacing(spacing.space_xs);
+        let mut empty = true;
+
+        for entry in self.visible() {
+            empty = false;
+
+            let status = match (entry.finished(), entry.exit_code) {
+                (false, _) => "Running".to_string(),
+                (true, Some(0)) => "OK".to_string(),
+                (true, Some(code)) => format!("Exit {code}"),
+                (true, None) => "Failed".to_string(),
+            };
+
+            let duration = match entry.duration {
+                Some(d) => format!("{} ms", d.as_millis()),
+                None => String::new(),
+            };
+
+            let details = match self.expanded == Some(entry.id) {
+                true => "Hide",
+                false => "Details",
+            };
+
+            let mut item = widget::Column::new().spacing(spacing.space_xxs).push(
+                widget::Row::new()
+                    .spacing(spacing.space_xs)
+                    .align_y(Alignment::Center)
+                    .push(
+                        widget::Column::new()
+                            .push(widget::text::body(entry.command.as_str()))
+                            .push(widget::text::caption(format!(
+                                "{} UTC · {status} · {duration}",
+                                entry.started_clock()
+                            )))
+                            .width(Length::Fill),
+                    )
+                    .push(button::text(details).on_press(LogMessage::Expand(entry.id).into())),
+            );
+
+            if self.expanded == Some(entry.id) {
+                item = item
+                    .push(widget::text::heading("Output"))
+                    .push(widget::text::monotext(entry.stdout.trim_end()))
+                    .push(widget::text::heading("Errors"))
+                    .push(widget::text::monotext(entry.stderr.trim_end()))
+                    .push(button::standard("Copy").on_press(LogMessage::Copy(entry.id).into()));
+            }
+
+            list = list.push(item);
+        }
+
+        if empty {
+            list = list.push(widget::text::body("No commands recorded"));
+        }
+
+        widget::Column::new()
+            .spacing(spacing.space_s)
+            .push(
+                text_input("Filter by command or output", &self.filter)
+                    .on_input(|t| LogMessage::FilterEdited(t).into()),
+            )
+            .push(
+                widget::Row::new()
+                    .spacing(spacing.space_s)
+                    .align_y(Alignment::Center)
+                    .push(
+                        toggler(self.failures_only)
+                            .label("Failures only")
+                            .on_toggle(|v| LogMessage::FailuresOnlyToggled(v).into()),
+                    )
+                    .push(widget::horizontal_space())
+                    .push(button::text("Copy all").on_press(LogMessage::CopyAll.into()))
+                    .push(button::destructive("Clear").on_press(LogMessage::Clear.into())),
+            )
+            .push(list)
+            .into()
+    }
+}
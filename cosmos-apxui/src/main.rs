@@ -1,10 +1,30 @@
 // SPDX-License-Identifier: MPL-2.0
 mod app;
 mod config;
+mod dialogs;
 mod i18n;
+mod launch;
+mod log;
+mod notifications;
 mod pages;
+mod settings;
 
 fn main() -> cosmic::iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.iter().any(|a| launch::HELP_FLAGS.contains(&a.as_str())) {
+        println!("{}", launch::USAGE);
+        return Ok(());
+    }
+
+    let launch = match launch::parse(args) {
+        Ok(launch) => launch,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+
     // Get the system's preferred languages.
     let requested_languages = i18n_embed::DesktopLanguageRequester::requested_languages();
 
@@ -18,6 +38,6 @@ fn main() -> cosmic::iced::Result {
             .min_height(180.0),
     );
 
-    // Starts the application's event loop with `()` as the application's flags.
-    cosmic::app::run::<app::AppModel>(settings, ())
+    // Starts the application's event loop, or hands the launch to an already running instance.
+    cosmic::app::run_single_instance::<app::AppModel>(settings, launch::Flags { launch })
 }
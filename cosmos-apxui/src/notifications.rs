@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use futures_util::{SinkExt, Stream, StreamExt};
+use tokio::sync::OnceCell;
+use tracing::{debug, warn};
+use zbus::{zvariant::Value, Connection};
+use zbus_macros::proxy;
+
+use crate::app::Message;
+
+// Action keys carry the subsystem to focus after this prefix.
+const FOCUS_ACTION: &str = "focus-subsystem:";
+
+#[proxy(
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications",
+    interface = "org.freedesktop.Notifications"
+)]
+pub trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+}
+
+/// A desktop notification about a finished operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notice {
+    pub summary: String,
+    pub body: String,
+    /// Subsystem the "Show" action focuses, if any.
+    pub subsystem: Option<String>,
+    pub failed: bool,
+}
+
+impl Notice {
+    /// A notice for an operation on a subsystem, worded by its outcome.
+    pub fn for_subsystem(operation: &str, subsystem: &str, res: &Result<(), String>) -> Self {
+        let (summary, body, failed) = match res {
+            Ok(_) => (format!("{operation} finished"), subsystem.to_string(), false),
+            Err(e) => (format!("{operation} failed"), format!("{subsystem}: {e}"), true),
+        };
+
+        Self {
+            summary,
+            body,
+            subsystem: Some(subsystem.to_string()),
+            failed,
+        }
+    }
+
+    /// Drops the "Show" action, e.g. when the subsystem is gone.
+    pub fn without_focus(mut self) -> Self {
+        self.subsystem = None;
+        self
+    }
+}
+
+static SESSION: OnceCell<Connection> = OnceCell::const_new();
+
+// The session bus, shared between notifications and their action listener.
+async fn session() -> Result<&'static Connection> {
+    Ok(SESSION.get_or_try_init(Connection::session).await?)
+}
+
+/// Sends `notice` through the notification daemon on `connection`.
+pub async fn send_on(connection: &Connection, notice: &Notice) -> Result<u32> {
+    let proxy = NotificationsProxy::new(connection).await?;
+
+    let action = notice
+        .subsystem
+        .as_ref()
+        .map(|s| format!("{FOCUS_ACTION}{s}"));
+    let actions: Vec<&str> = match &action {
+        Some(key) => vec![key.as_str(), "Show"],
+        None => vec![],
+    };
+
+    let mut hints = HashMap::new();
+    hints.insert(
+        "desktop-entry",
+        Value::from(<crate::app::AppModel as cosmic::Application>::APP_ID),
+    );
+    // 1 is normal urgency, 2 critical.
+    hints.insert("urgency", Value::U8(if notice.failed { 2 } else { 1 }));
+
+    let id = proxy
+        .notify(
+            "Apx",
+            0,
+            "utilities-terminal",
+            &notice.summary,
+            &notice.body,
+            &actions,
+            hints,
+            -1,
+        )
+        .await?;
+
+    Ok(id)
+}
+
+/// Sends `notice` on the session bus, logging rather than failing the operation it reports on.
+pub fn send(notice: Notice) -> cosmic::app::Task<Message> {
+    cosmic::app::Task::perform(
+        async move {
+            let res = match session().await {
+                Ok(connection) => send_on(connection, &notice).await,
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = res {
+                warn!("Could not send notification: {e}");
+            }
+        },
+        |_| Message::NotificationSent.into(),
+    )
+}
+
+/// The subsystem a notification action asks to focus, if it's one of ours.
+pub fn focus_target(action_key: &str) -> Option<&str> {
+    action_key.strip_prefix(FOCUS_ACTION)
+}
+
+/// The action keys picked on notifications from the daemon on `connection`.
+pub async fn receive_actions(connection: &Connection) -> Result<impl Stream<Item = String>> {
+    let proxy = NotificationsProxy::new(connection).await?;
+    let signals = proxy.receive_action_invoked().await?;
+
+    Ok(signals.filter_map(|signal| async move {
+        let args = signal.args().ok()?;
+        debug!("notification {} action {}", args.id, args.action_key);
+
+        Some(args.action_key.to_string())
+    }))
+}
+
+/// Emits `Message::NotificationAction` for every action the user picks on a notification.
+pub fn actions() -> impl Stream<Item = Message> {
+    cosmic::iced::stream::channel(4, |mut output| async move {
+        let res: Result<()> = async {
+            let mut keys = Box::pin(receive_actions(session().await?).await?);
+
+            while let Some(key) = keys.next().await {
+                let _ = output.send(Message::NotificationAction(key)).await;
+            }
+
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = res {
+            warn!("Not listening for notification actions: {e}");
+        }
+
+        futures_util::future::pending::<()>().await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+    use zbus::{connection, interface, zvariant::OwnedValue};
+
+    const PATH: &str = "/org/freedesktop/Notifications";
+
+    // A dbus-daemon of our own, killed when dropped.
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        // None when dbus-daemon isn't installed.
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+
+        async fn connect(&self) -> Connection {
+            connection::Builder::address(self.address.as_str())
+                .unwrap()
+                .build()
+                .await
+                .unwrap()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Notified {
+        app_name: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        desktop_entry: String,
+        urgency: u8,
+    }
+
+    // Records what it's asked to show, standing in for the notification daemon.
+    struct StandIn(Arc<Mutex<Vec<Notified>>>);
+
+    #[interface(name = "org.freedesktop.Notifications")]
+    impl StandIn {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            app_name: String,
+            _replaces_id: u32,
+            _app_icon: String,
+            summary: String,
+            body: String,
+            actions: Vec<String>,
+            hints: HashMap<String, OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let hint = |key: &str| hints.get(key).and_then(|v| v.try_clone().ok());
+
+            let mut notified = self.0.lock().unwrap();
+            notified.push(Notified {
+                app_name,
+                summary,
+                body,
+                actions,
+                desktop_entry: hint("desktop-entry")
+                    .and_then(|v| String::try_from(v).ok())
+                    .unwrap_or_default(),
+                urgency: hint("urgency").and_then(|v| u8::try_from(v).ok()).unwrap_or_default(),
+            });
+
+            notified.len() as u32
+        }
+    }
+
+    async fn stand_in(bus: &PrivateBus) -> (Connection, Arc<Mutex<Vec<Notified>>>) {
+        let notified = Arc::new(Mutex::new(vec![]));
+
+        let daemon = connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .name("org.freedesktop.Notifications")
+            .unwrap()
+            .serve_at(PATH, StandIn(notified.clone()))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        (daemon, notified)
+    }
+
+    #[tokio::test]
+    async fn notices_are_sent_with_focus_actions() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon isn't installed, skipping");
+            return;
+        };
+        let (_daemon, notified) = stand_in(&bus).await;
+        let client = bus.connect().await;
+
+        let reset = Notice::for_subsystem("Reset", "box", &Ok(()));
+        let failed = Notice::for_subsystem("Delete", "box", &Err("busy".into()));
+        let deleted = Notice::for_subsystem("Delete", "box", &Ok(())).without_focus();
+
+        assert_eq!(send_on(&client, &reset).await.unwrap(), 1);
+        send_on(&client, &failed).await.unwrap();
+        send_on(&client, &deleted).await.unwrap();
+
+        let app_id = <crate::app::AppModel as cosmic::Application>::APP_ID;
+        let notify = |summary: &str, body: &str, actions: &[&str], urgency| Notified {
+            app_name: "Apx".into(),
+            summary: summary.into(),
+            body: body.into(),
+            actions: actions.iter().map(|a| a.to_string()).collect(),
+            desktop_entry: app_id.into(),
+            urgency,
+        };
+
+        assert_eq!(
+            *notified.lock().unwrap(),
+            vec![
+                notify("Reset finished", "box", &["focus-subsystem:box", "Show"], 1),
+                notify("Delete failed", "box: busy", &["focus-subsystem:box", "Show"], 2),
+                notify("Delete finished", "box", &[], 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn invoked_actions_map_to_the_subsystem_to_focus() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon isn't installed, skipping");
+            return;
+        };
+        let (daemon, _) = stand_in(&bus).await;
+        let client = bus.connect().await;
+
+        let mut keys = Box::pin(receive_actions(&client).await.unwrap());
+
+        for key in ["default", "focus-subsystem:box"] {
+            daemon
+                .emit_signal(
+                    None::<()>,
+                    PATH,
+                    "org.freedesktop.Notifications",
+                    "ActionInvoked",
+                    &(1u32, key),
+                )
+                .await
+                .unwrap();
+        }
+
+        let default = keys.next().await.unwrap();
+        let focus = keys.next().await.unwrap();
+
+        assert_eq!(focus_target(&default), None);
+        assert_eq!(focus_target(&focus), Some("box"));
+    }
+}
@@ -1,7 +1,15 @@
-use cosmic::widget::{nav_bar, segmented_button::Entity};
+use cosmic::{
+    app::Task,
+    cosmic_theme::Spacing,
+    iced::Alignment,
+    widget::{self, button, icon, nav_bar, segmented_button::Entity},
+    Element,
+};
 
 use crate::app::Message;
+use crate::config::Config;
 
+pub(crate) mod packages;
 pub(crate) mod pkgmanagers;
 pub(crate) mod stacks;
 pub(crate) mod subsystems;
@@ -11,14 +19,110 @@ pub(crate) mod subsystems;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Page {
     Subsystems,
+    Packages,
     PkgManagers,
     Stacks,
 }
 
+/// Where a page is in fetching its items.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum LoadState {
+    #[default]
+    Loading,
+    Loaded,
+    Failed(String),
+}
+
 pub trait PageModel {
     fn view(&self) -> cosmic::Element<'_, Message>;
+    /// A modal dialog the page wants shown over the window, if any.
+    fn dialog(&self) -> Option<cosmic::Element<'_, Message>> {
+        None
+    }
     fn current_items(&self) -> &nav_bar::Model;
-    fn update_items(&mut self);
-    fn on_select(&mut self, item: Entity);
-    fn on_message(&mut self, message: Message);
+    fn load_state(&self) -> &LoadState;
+    /// Starts reloading the page's items. Results of earlier, superseded loads are discarded.
+    fn update_items(&mut self) -> Task<Message>;
+    fn on_select(&mut self, item: Entity) -> Task<Message>;
+    fn on_message(&mut self, message: Message) -> Task<Message>;
+    /// Picks up changed settings, reloading if they affect what's listed.
+    fn apply_config(&mut self, _config: &Config) -> Task<Message> {
+        Task::none()
+    }
+}
+
+/// Runs a blocking apx-shim call off the event loop, flattening errors to strings.
+pub(crate) async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> Result<T, String> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(res) => res.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Loads a page's items in the background, tagging the result with the request's generation.
+pub(crate) fn load<T: Send + 'static>(
+    generation: u64,
+    f: impl FnOnce() -> anyhow::Result<Vec<T>> + Send + 'static,
+    on_loaded: impl FnOnce(u64, Result<Vec<T>, String>) -> Message + Send + 'static,
+) -> Task<Message> {
+    Task::perform(blocking(f), move |res| on_loaded(generation, res).into())
+}
+
+/// Builds a nav model from loaded items, activating the one named `select`.
+pub(crate) fn fill_nav<T: 'static>(
+    items: Vec<T>,
+    name: impl Fn(&T) -> String,
+    select: Option<&str>,
+) -> nav_bar::Model {
+    let mut model = nav_bar::Model::default();
+
+    for item in items {
+        let text = name(&item);
+        let selected = select == Some(text.as_str());
+        let entry = model.insert().text(text).data::<T>(item);
+
+        if selected {
+            entry.activate();
+        }
+    }
+
+    model
+}
+
+/// Finds the nav entry whose data matches `name`.
+pub(crate) fn find_by_name<T: 'static>(
+    model: &nav_bar::Model,
+    name: impl Fn(&T) -> &str,
+    wanted: &str,
+) -> Option<Entity> {
+    model
+        .iter()
+        .find(|e| model.data::<T>(*e).is_some_and(|d| name(d) == wanted))
+}
+
+/// A "Loading…" label with a static working icon while loading, or the error and a
+/// retry button after a failed load.
+pub(crate) fn load_state_view(state: &LoadState, retry: Message) -> Option<Element<'_, Message>> {
+    let spacing = Spacing::default();
+
+    match state {
+        LoadState::Loaded => None,
+        LoadState::Loading => Some(
+            widget::Row::new()
+                .spacing(spacing.space_xs)
+                .align_y(Alignment::Center)
+                .push(icon::from_name("process-working-symbolic").size(16).icon())
+                .push(widget::text::body("Loading…"))
+                .into(),
+        ),
+        LoadState::Failed(e) => Some(
+            widget::Column::new()
+                .spacing(spacing.space_xs)
+                .push(widget::text::body(format!("Failed to load: {e}")))
+                .push(button::standard("Retry").on_press(retry))
+                .into(),
+        ),
+    }
 }
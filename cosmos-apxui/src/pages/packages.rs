@@ -0,0 +1,491 @@
+use super::{blocking, fill_nav, load, LoadState, PageModel};
+use crate::app::Message;
+use crate::notifications::{self, Notice};
+use apx_shim::{Package, Subsystem};
+use cosmic::{
+    self,
+    app::Task,
+    cosmic_theme::{self, Spacing},
+    iced::{Alignment, Length},
+    iced_widget, theme,
+    widget::{self, button, nav_bar},
+};
+
+// Rendering thousands of rows at once makes the page sluggish, filter to see more.
+const MAX_ROWS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageAction {
+    Install,
+    Remove,
+    Purge,
+}
+
+impl PackageAction {
+    fn verb(&self) -> &'static str {
+        match self {
+            PackageAction::Install => "Install",
+            PackageAction::Remove => "Remove",
+            PackageAction::Purge => "Purge",
+        }
+    }
+}
+
+pub struct PackagesModel {
+    nav_bar: nav_bar::Model,
+    installed: Option<Vec<Package>>,
+    filter: String,
+    search_input: String,
+    search_results: Vec<String>,
+    searching: bool,
+    selected_package: Option<String>,
+    details: Option<String>,
+    pending: Option<(PackageAction, String)>,
+    busy: Option<String>,
+    error_status: Option<String>,
+    state: LoadState,
+    generation: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum PackagesMessage {
+    SubsystemsLoaded(u64, Result<Vec<Subsystem>, String>),
+    Refresh,
+    InstalledLoaded(String, Result<Vec<Package>, String>),
+    FilterEdited(String),
+    SearchEdited(String),
+    Search,
+    SearchResults(String, Result<Vec<String>, String>),
+    ShowDetails(String),
+    DetailsLoaded(String, String, Result<String, String>),
+    CloseDetails,
+    Request(PackageAction, String),
+    Confirm,
+    Cancel,
+    ActionFinished(String, Result<(), String>),
+    CloseError,
+}
+
+impl Into<Message> for PackagesMessage {
+    fn into(self) -> Message {
+        Message::Packages(self)
+    }
+}
+
+impl PackagesModel {
+    pub fn new() -> Self {
+        Self {
+            nav_bar: nav_bar::Model::default(),
+            installed: None,
+            filter: String::new(),
+            search_input: String::new(),
+            search_results: vec![],
+            searching: false,
+            selected_package: None,
+            details: None,
+            pending: None,
+            busy: None,
+            error_status: None,
+            state: LoadState::default(),
+            generation: 0,
+        }
+    }
+
+    fn active_name(&self) -> Option<String> {
+        self.nav_bar
+            .active_data::<Subsystem>()
+            .map(|s| s.name.clone())
+    }
+
+    fn load_installed(&mut self) -> Task<Message> {
+        let subsystem = match self.nav_bar.active_data::<Subsystem>() {
+            Some(s) => s.clone(),
+            None => return Task::none(),
+        };
+
+        self.installed = None;
+
+        Task::perform(
+            async move {
+                let name = subsystem.name.clone();
+                (name, blocking(move || subsystem.list_installed()).await)
+            },
+            |(name, res)| Message::Packages(PackagesMessage::InstalledLoaded(name, res)).into(),
+        )
+    }
+
+    fn installed_view(&self) -> cosmic::Element<'_, Message> {
+        let spacing = Spacing::default();
+
+        let installed = match &self.installed {
+            Some(i) => i,
+            None => return widget::text::body("Loading installed packages…").into(),
+        };
+
+        let filter = self.filter.to_lowercase();
+        let matches: Vec<&Package> = installed
+            .iter()
+            .filter(|p| filter.is_empty() || p.name.to_lowercase().contains(&filter))
+            .collect();
+
+        let mut list = widget::Column::new().spacing(spacing.space_xxs);
+
+        for package in matches.iter().take(MAX_ROWS) {
+            list = list.push(
+                widget::Row::new()
+                    .spacing(spacing.space_xs)
+                    .align_y(Alignment::Center)
+                    .push(widget::text::body(package.name.as_str()).width(Length::FillPortion(2)))
+                    .push(widget::text::caption(package.version.as_str()).width(Length::FillPortion(1)))
+                    .push(
+                        button::text("Details")
+                            .on_press(PackagesMessage::ShowDetails(package.name.clone()).into()),
+                    )
+                    .push(button::text("Remove").on_press(
+                        PackagesMessage::Request(PackageAction::Remove, package.name.clone()).into(),
+                    ))
+                    .push(button::text("Purge").on_press(
+                        PackagesMessage::Request(PackageAction::Purge, package.name.clone()).into(),
+                    )),
+            );
+        }
+
+        let count = match matches.len() > MAX_ROWS {
+            true => format!("Showing {MAX_ROWS} of {} packages", matches.len()),
+            false => format!("{} packages", matches.len()),
+        };
+
+        widget::Column::new()
+            .spacing(spacing.space_s)
+            .push(
+                widget::TextInput::new("Filter installed packages", &self.filter)
+                    .on_input(|t| PackagesMessage::FilterEdited(t).into()),
+            )
+            .push(widget::text::caption(count))
+            .push(list)
+            .into()
+    }
+}
+
+impl PageModel for PackagesModel {
+    fn view(&self) -> cosmic::Element<'_, Message> {
+        let spacing = Spacing::default();
+
+        let data = match self.nav_bar.active_data::<Subsystem>() {
+            Some(data) => data,
+            None => {
+                return widget::Column::new()
+                    .push(widget::Text::new("No subsystem selected").size(24))
+                    .into()
+            }
+        };
+
+        let mut content = widget::Column::new().push(
+            iced_widget::row![
+                widget::Text::new(&data.name).size(24).width(Length::Fill),
+                button::link("Refresh").on_press(PackagesMessage::Refresh.into()),
+            ]
+            .align_y(Alignment::Center)
+            .padding([0, 0, 20, 0]),
+        );
+
+        if let Some(error) = &self.error_status {
+            content = content.push(cosmos_common::error(error, PackagesMessage::CloseError.into()));
+        }
+
+        if let Some(busy) = &self.busy {
+            content = content.push(widget::text::body(busy.as_str()));
+        }
+
+        let mut search = button::standard("Search");
+        if !self.searching && !self.search_input.trim().is_empty() {
+            search = search.on_press(PackagesMessage::Search.into());
+        }
+
+        let mut search_column = widget::Column::new().spacing(spacing.space_s).push(
+            widget::Row::new()
+                .spacing(spacing.space_xs)
+                .align_y(Alignment::Center)
+                .push(
+                    widget::TextInput::new("Search available packages", &self.search_input)
+                        .on_input(|t| PackagesMessage::SearchEdited(t).into()),
+                )
+                .push(search),
+        );
+
+        if self.searching {
+            search_column = search_column.push(widget::text::caption("Searching…"));
+        }
+
+        for result in self.search_results.iter().take(MAX_ROWS) {
+            search_column = search_column.push(
+                widget::Row::new()
+                    .spacing(spacing.space_xs)
+                    .align_y(Alignment::Center)
+                    .push(widget::text::body(result.as_str()).width(Length::Fill))
+                    .push(
+                        button::text("Details")
+                            .on_press(PackagesMessage::ShowDetails(result.clone()).into()),
+                    )
+                    .push(button::text("Install").on_press(
+                        PackagesMessage::Request(PackageAction::Install, result.clone()).into(),
+                    )),
+            );
+        }
+
+        let mut sections = iced_widget::column![
+            widget::Text::new("Search").size(18),
+            widget::Container::new(search_column.padding(20))
+                .style(|_| theme::Container::primary(&cosmic_theme::Theme::default()))
+                .width(Length::Fill),
+        ]
+        .spacing(spacing.space_xs);
+
+        if let Some(package) = &self.selected_package {
+            let details = match &self.details {
+                Some(d) => d.as_str(),
+                None => "Loading…",
+            };
+
+            sections = sections
+                .push(
+                    iced_widget::row![
+                        widget::Text::new(package.as_str()).size(18).width(Length::Fill),
+                        button::link("Close").on_press(PackagesMessage::CloseDetails.into()),
+                    ]
+                    .align_y(Alignment::Center),
+                )
+                .push(
+                    widget::Container::new(widget::text::body(details))
+                        .padding(20)
+                        .style(|_| theme::Container::primary(&cosmic_theme::Theme::default()))
+                        .width(Length::Fill),
+                );
+        }
+
+        sections = sections.push(widget::Text::new("Installed").size(18)).push(
+            widget::Container::new(widget::Container::new(self.installed_view()).padding(20))
+                .style(|_| theme::Container::primary(&cosmic_theme::Theme::default()))
+                .width(Length::Fill),
+        );
+
+        content
+            .push(iced_widget::scrollable(sections.padding([0, 20, 0, 0])).height(Length::Fill))
+            .into()
+    }
+
+    fn dialog(&self) -> Option<cosmic::Element<'_, Message>> {
+        let (action, package) = self.pending.as_ref()?;
+        let subsystem = self.active_name().unwrap_or_default();
+
+        let body = match action {
+            PackageAction::Install => format!("{package} will be installed in {subsystem}."),
+            PackageAction::Remove => format!("{package} will be removed from {subsystem}."),
+            PackageAction::Purge => format!(
+                "{package} will be removed from {subsystem}, along with its configuration files."
+            ),
+        };
+
+        let primary = match action {
+            PackageAction::Install => button::suggested(action.verb()),
+            _ => button::destructive(action.verb()),
+        };
+
+        Some(
+            widget::dialog()
+                .title(format!("{} {package}?", action.verb()))
+                .body(body)
+                .primary_action(primary.on_press(PackagesMessage::Confirm.into()))
+                .secondary_action(
+                    button::standard("Cancel").on_press(PackagesMessage::Cancel.into()),
+                )
+                .into(),
+        )
+    }
+
+    fn current_items(&self) -> &nav_bar::Model {
+        &self.nav_bar
+    }
+
+    fn load_state(&self) -> &LoadState {
+        &self.state
+    }
+
+    fn update_items(&mut self) -> Task<Message> {
+        self.generation += 1;
+        self.state = LoadState::Loading;
+
+        load(self.generation, apx_shim::Subsystem::get_all, |generation, res| {
+            Message::Packages(PackagesMessage::SubsystemsLoaded(generation, res))
+        })
+    }
+
+    fn on_select(&mut self, item: widget::segmented_button::Entity) -> Task<Message> {
+        self.nav_bar.activate(item);
+
+        self.filter.clear();
+        self.search_results.clear();
+        self.searching = false;
+        self.selected_package = None;
+        self.details = None;
+        self.error_status = None;
+
+        self.load_installed()
+    }
+
+    fn on_message(&mut self, message: Message) -> Task<Message> {
+        let msg = match message {
+            Message::Packages(msg) => msg,
+            _ => return Task::none(),
+        };
+
+        let active = self.active_name();
+
+        match msg {
+            PackagesMessage::SubsystemsLoaded(generation, res) => {
+                //A newer load has been started since, so this result is stale.
+                if generation != self.generation {
+                    return Task::none();
+                }
+
+                match res {
+                    Ok(data) => {
+                        self.nav_bar = fill_nav(data, |s: &Subsystem| s.name.clone(), active.as_deref());
+                        self.state = LoadState::Loaded;
+
+                        return self.load_installed();
+                    }
+                    Err(e) => self.state = LoadState::Failed(e),
+                }
+            }
+            PackagesMessage::Refresh => return self.load_installed(),
+            PackagesMessage::InstalledLoaded(name, res) => {
+                //Drop results for a subsystem that is no longer selected.
+                if active.as_ref() != Some(&name) {
+                    return Task::none();
+                }
+
+                match res {
+                    Ok(packages) => self.installed = Some(packages),
+                    Err(e) => {
+                        self.installed = Some(vec![]);
+                        self.error_status = Some(format!("Could not list packages: {e}"));
+                    }
+                }
+            }
+            PackagesMessage::FilterEdited(t) => self.filter = t,
+            PackagesMessage::SearchEdited(t) => self.search_input = t,
+            PackagesMessage::Search => {
+                let subsystem = match self.nav_bar.active_data::<Subsystem>() {
+                    Some(s) => s.clone(),
+                    None => return Task::none(),
+                };
+                let query = self.search_input.trim().to_string();
+
+                self.searching = true;
+                self.search_results.clear();
+
+                return Task::perform(
+                    async move {
+                        let name = subsystem.name.clone();
+                        (name, blocking(move || subsystem.search(&query)).await)
+                    },
+                    |(name, res)| Message::Packages(PackagesMessage::SearchResults(name, res)).into(),
+                );
+            }
+            PackagesMessage::SearchResults(name, res) => {
+                self.searching = false;
+
+                if active.as_ref() != Some(&name) {
+                    return Task::none();
+                }
+
+                match res {
+                    Ok(results) => self.search_results = results,
+                    Err(e) => self.error_status = Some(format!("Search failed: {e}")),
+                }
+            }
+            PackagesMessage::ShowDetails(package) => {
+                let subsystem = match self.nav_bar.active_data::<Subsystem>() {
+                    Some(s) => s.clone(),
+                    None => return Task::none(),
+                };
+
+                self.selected_package = Some(package.clone());
+                self.details = None;
+
+                return Task::perform(
+                    async move {
+                        let name = subsystem.name.clone();
+                        let p = package.clone();
+                        (name, package, blocking(move || subsystem.show(&p)).await)
+                    },
+                    |(name, package, res)| {
+                        Message::Packages(PackagesMessage::DetailsLoaded(name, package, res)).into()
+                    },
+                );
+            }
+            PackagesMessage::DetailsLoaded(name, package, res) => {
+                if active.as_ref() != Some(&name) || self.selected_package.as_ref() != Some(&package) {
+                    return Task::none();
+                }
+
+                self.details = Some(match res {
+                    Ok(details) => details,
+                    Err(e) => format!("Could not load details: {e}"),
+                });
+            }
+            PackagesMessage::CloseDetails => {
+                self.selected_package = None;
+                self.details = None;
+            }
+            PackagesMessage::Request(action, package) => self.pending = Some((action, package)),
+            PackagesMessage::Cancel => self.pending = None,
+            PackagesMessage::Confirm => {
+                let (action, package) = match self.pending.take() {
+                    Some(p) => p,
+                    None => return Task::none(),
+                };
+                let subsystem = match self.nav_bar.active_data::<Subsystem>() {
+                    Some(s) => s.clone(),
+                    None => return Task::none(),
+                };
+
+                self.busy = Some(format!("{}ing {package}…", action.verb().trim_end_matches('e')));
+
+                return Task::perform(
+                    async move {
+                        let name = subsystem.name.clone();
+                        let packages = vec![package];
+                        let res = blocking(move || match action {
+                            PackageAction::Install => subsystem.install(&packages),
+                            PackageAction::Remove => subsystem.remove_packages(&packages),
+                            PackageAction::Purge => subsystem.purge(&packages),
+                        })
+                        .await;
+                        (name, res)
+                    },
+                    |(name, res)| Message::Packages(PackagesMessage::ActionFinished(name, res)).into(),
+                );
+            }
+            PackagesMessage::ActionFinished(name, res) => {
+                self.busy = None;
+
+                let notice = notifications::send(Notice::for_subsystem("Package changes", &name, &res));
+
+                if let Err(e) = res {
+                    self.error_status = Some(e);
+                }
+
+                if active.as_ref() == Some(&name) {
+                    return Task::batch([notice, self.load_installed()]);
+                }
+
+                return notice;
+            }
+            PackagesMessage::CloseError => self.error_status = None,
+        }
+
+        Task::none()
+    }
+}
@@ -1,6 +1,7 @@
-use apx_shim::PackageManager;
+use apx_shim::{PackageManager, Stack};
 use cosmic::{
     self,
+    app::Task,
     cosmic_theme::{self, Spacing},
     iced::{Alignment, Length},
     iced_widget::{self},
@@ -11,23 +12,87 @@ use cosmic::{
 use tracing::{debug, warn};
 
 use crate::app::Message;
+use crate::config::Config;
+use crate::dialogs::confirm::{ConfirmDialog, ConfirmMessage};
 
-use super::PageModel;
+use super::{blocking, fill_nav, find_by_name, load, LoadState, PageModel};
 
 pub struct PkgManagerModel {
     nav_bar: nav_bar::Model,
+    error_status: Option<String>,
+    busy: bool,
+    state: LoadState,
+    generation: u64,
+    select_after_load: Option<String>,
+    show_built_in: bool,
+    confirm_destructive: bool,
+    /// A delete waiting on the user.
+    confirm: Option<(PackageManager, ConfirmDialog)>,
 }
 
 impl PkgManagerModel {
     pub fn new() -> Self {
         Self {
             nav_bar: nav_bar::Model::default(),
+            error_status: None,
+            busy: false,
+            state: LoadState::default(),
+            generation: 0,
+            select_after_load: None,
+            show_built_in: true,
+            confirm_destructive: true,
+            confirm: None,
         }
     }
+
+    fn delete(&mut self, pkg_manager: PackageManager, force: bool) -> Task<Message> {
+        self.busy = true;
+
+        Task::perform(blocking(move || pkg_manager.remove(force)), |res| {
+            Message::PkgManager(PkgManagerMessage::Deleted(res)).into()
+        })
+    }
+
+    /// Asks before deleting, listing the stacks that use the package manager.
+    fn ask_delete(&mut self, pkg_manager: PackageManager) -> Task<Message> {
+        let name = pkg_manager.name.clone();
+
+        let dialog = ConfirmDialog::new(
+            format!("Delete {name}?"),
+            "Delete",
+            &name,
+            "The package manager is removed from apx. Stacks using it can't create \
+             subsystems or manage packages until they're switched to another one.",
+        )
+        .with_impact("Stacks using this package manager")
+        .irreversible();
+
+        self.confirm = Some((pkg_manager, dialog));
+
+        let target = name.clone();
+        Task::perform(
+            blocking(move || {
+                Ok(Stack::get_all()?
+                    .into_iter()
+                    .filter(|s| s.package_manager == name)
+                    .map(|s| s.name)
+                    .collect())
+            }),
+            move |res| {
+                Message::PkgManager(PkgManagerMessage::Confirm(ConfirmMessage::ImpactLoaded(
+                    target, res,
+                )))
+                .into()
+            },
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum PkgManagerMessage {
+    Loaded(u64, Result<Vec<PackageManager>, String>),
+    /// Selects the named package manager, reloading first if it isn't listed yet.
+    Focus(String),
     AutoRemoveEdited(String),
     CleanEdited(String),
     InstallEdited(String),
@@ -39,8 +104,12 @@ pub enum PkgManagerMessage {
     UpdateEdited(String),
     UpgradeEdited(String),
     Save,
+    Saved(Result<(), String>),
     Reset,
     Delete,
+    Deleted(Result<(), String>),
+    Confirm(ConfirmMessage),
+    CloseError,
 }
 
 impl Into<Message> for PkgManagerMessage {
@@ -110,21 +179,33 @@ impl PageModel for PkgManagerModel {
                 column = column.push(element); // Reassign the column
             }
 
-            iced_widget::column![
+            let mut save = button::link("Save");
+            let mut delete = button::destructive("Delete");
+            if !self.busy {
+                save = save.on_press(PkgManagerMessage::Save.into());
+                delete = delete.on_press(PkgManagerMessage::Delete.into());
+            }
+
+            let mut content = iced_widget::column![iced_widget::row![
+                widget::Text::new(&data.name).size(24).width(Length::Fill),
                 iced_widget::row![
-                    widget::Text::new(&data.name).size(24).width(Length::Fill),
-                    iced_widget::row![
-                        button::link("Reset").on_press(PkgManagerMessage::Reset.into()),
-                        button::link("Save").on_press(PkgManagerMessage::Save.into()),
-                        button::destructive("Delete").on_press(PkgManagerMessage::Delete.into()),
-                    ]
-                    .spacing(20)
-                    .width(Length::Shrink)
-                    .align_y(Alignment::Center)
+                    button::link("Reset").on_press(PkgManagerMessage::Reset.into()),
+                    save,
+                    delete,
                 ]
-                .padding([0, 0, 20, 0])
-                .height(Length::Shrink),
-                iced_widget::scrollable(
+                .spacing(20)
+                .width(Length::Shrink)
+                .align_y(Alignment::Center)
+            ]
+            .padding([0, 0, 20, 0])
+            .height(Length::Shrink)];
+
+            if let Some(error) = &self.error_status {
+                content = content.push(cosmos_common::error(error, PkgManagerMessage::CloseError.into()));
+            }
+
+            content
+                .push(iced_widget::scrollable(
                     iced_widget::column![
                         widget::Text::new("Commands").size(18),
                         widget::Container::new(column.spacing(20).padding(20))
@@ -132,13 +213,17 @@ impl PageModel for PkgManagerModel {
                     ]
                     .spacing(Spacing::default().space_xs)
                 )
-                .height(Length::Fill),
-            ]
-            .into()
-        } else {
-            widget::Column::new()
-                .push(widget::Text::new("No package manager selected").size(24))
+                .height(Length::Fill))
                 .into()
+        } else {
+            let mut column = widget::Column::new()
+                .push(widget::Text::new("No package manager selected").size(24));
+
+            if let Some(error) = &self.error_status {
+                column = column.push(cosmos_common::error(error, PkgManagerMessage::CloseError.into()));
+            }
+
+            column.into()
         }
     }
 
@@ -146,96 +231,176 @@ impl PageModel for PkgManagerModel {
         &self.nav_bar
     }
 
-    fn update_items(&mut self) {
-        let data = apx_shim::PackageManager::get_all();
-        let nav = match data {
-            Ok(data) => {
-                let mut items = nav_bar::Model::default();
-                for item in data {
-                    items
-                        .insert()
-                        .text(item.name.clone())
-                        .data::<PackageManager>(item);
-                }
-                items
-            }
-            Err(_) => nav_bar::Model::default(),
-        };
+    fn load_state(&self) -> &LoadState {
+        &self.state
+    }
+
+    fn update_items(&mut self) -> Task<Message> {
+        if self.select_after_load.is_none() {
+            self.select_after_load = self
+                .nav_bar
+                .active_data::<PackageManager>()
+                .map(|p| p.name.clone());
+        }
+
+        self.generation += 1;
+        self.state = LoadState::Loading;
 
-        self.nav_bar = nav;
+        load(self.generation, apx_shim::PackageManager::get_all, |generation, res| {
+            Message::PkgManager(PkgManagerMessage::Loaded(generation, res))
+        })
     }
 
-    fn on_select(&mut self, item: widget::segmented_button::Entity) {
+    fn on_select(&mut self, item: widget::segmented_button::Entity) -> Task<Message> {
         self.nav_bar.activate(item);
+
+        Task::none()
     }
 
-    fn on_message(&mut self, message: Message) {
+    fn dialog(&self) -> Option<cosmic::Element<'_, Message>> {
+        self.confirm
+            .as_ref()
+            .map(|(_, dialog)| dialog.view(|m| PkgManagerMessage::Confirm(m).into()))
+    }
 
-        let data = match self.nav_bar.active_data_mut::<PackageManager>() {
-            Some(data) => data,
-            None => {
-                warn!("No active data found");
-                return;
-            },
+    fn apply_config(&mut self, config: &Config) -> Task<Message> {
+        self.confirm_destructive = config.confirm_destructive;
+
+        if self.show_built_in == config.show_built_in_pkg_managers {
+            return Task::none();
+        }
+
+        self.show_built_in = config.show_built_in_pkg_managers;
+        self.update_items()
+    }
+
+    fn on_message(&mut self, message: Message) -> Task<Message> {
+        let msg = match message {
+            Message::PkgManager(msg) => msg,
+            _ => return Task::none(),
         };
 
-        match message {
-            Message::PkgManager(msg) => match msg {
-                PkgManagerMessage::AutoRemoveEdited(s) => data.cmd_auto_remove = s,
-                PkgManagerMessage::CleanEdited(s) => data.cmd_clean = s,
-                PkgManagerMessage::InstallEdited(s) => data.cmd_install = s,
-                PkgManagerMessage::ListEdited(s) => data.cmd_list = s,
-                PkgManagerMessage::PurgeEdited(s) => data.cmd_purge = s,
-                PkgManagerMessage::RemoveEdited(s) => data.cmd_remove = s,
-                PkgManagerMessage::SearchEdited(s) => data.cmd_search = s,
-                PkgManagerMessage::ShowEdited(s) => data.cmd_show = s,
-                PkgManagerMessage::UpdateEdited(s) => data.cmd_update = s,
-                PkgManagerMessage::UpgradeEdited(s) => data.cmd_upgrade = s,
-                PkgManagerMessage::Save => {
-                    // match data.create()
-                    // {
-                    //      Ok(_) => return,
-                    //Err(_) =>
-                    match data.update() {
-                        Ok(_) => return,
-                        Err(e) => todo!("Handle error on saving: {e}"),
+        match msg {
+            PkgManagerMessage::Loaded(generation, res) => {
+                //A newer load has been started since, so this result is stale.
+                if generation != self.generation {
+                    return Task::none();
+                }
+
+                match res {
+                    Ok(mut data) => {
+                        if !self.show_built_in {
+                            data.retain(|p| !p.built_in);
+                        }
+
+                        let select = self.select_after_load.take();
+                        self.nav_bar =
+                            fill_nav(data, |p: &PackageManager| p.name.clone(), select.as_deref());
+                        self.state = LoadState::Loaded;
                     }
-                    //   }
+                    Err(e) => self.state = LoadState::Failed(e),
                 }
-                PkgManagerMessage::Reset => {
-
-                    let name = match self
-                    .nav_bar
-                    .active_data_mut::<PackageManager>() {
-                        Some(data) => data.name.clone(),
-                        None => {
-                            warn!("No active data found");
-                            return;
-                        },
-                    };
-
-                    self.update_items();
-                    let matched = self
-                        .nav_bar
-                        .iter()
-                        .find(|e| self.nav_bar.data::<PackageManager>(*e).unwrap().name == name);
-
-                    match matched {
-                        Some(m) => self.nav_bar.activate(m),
-                        None => todo!("Handle no match on reset"),
+
+                return Task::none();
+            }
+            PkgManagerMessage::Focus(name) => {
+                return match find_by_name(&self.nav_bar, |p: &PackageManager| &p.name, &name) {
+                    Some(entity) => self.on_select(entity),
+                    None => {
+                        self.select_after_load = Some(name);
+                        self.update_items()
                     }
+                };
+            }
+            PkgManagerMessage::Saved(res) => {
+                self.busy = false;
+
+                match res {
+                    Ok(_) => self.error_status = None,
+                    Err(e) => self.error_status = Some(format!("Error on saving: {e}")),
                 }
-                PkgManagerMessage::Delete => match data.remove(true) {
-                    Ok(_) => {
-                        debug!("Successfully deleted");
 
-                        self.nav_bar.remove(self.nav_bar.active());
+                return Task::none();
+            }
+            PkgManagerMessage::Deleted(res) => {
+                self.busy = false;
+
+                match res {
+                    Ok(_) => debug!("Successfully deleted"),
+                    Err(e) => self.error_status = Some(format!("Error on delete: {e}")),
+                }
+
+                return self.update_items();
+            }
+            PkgManagerMessage::Confirm(ConfirmMessage::Cancel) => {
+                self.confirm = None;
+                return Task::none();
+            }
+            PkgManagerMessage::Confirm(msg) => {
+                let confirmed = match self.confirm.as_mut() {
+                    Some((_, dialog)) => dialog.update(msg),
+                    None => false,
+                };
+
+                if confirmed {
+                    if let Some((pkg_manager, dialog)) = self.confirm.take() {
+                        return self.delete(pkg_manager, dialog.force());
                     }
-                    Err(_) => todo!(),
-                },
+                }
+
+                return Task::none();
+            }
+            PkgManagerMessage::CloseError => {
+                self.error_status = None;
+                return Task::none();
+            }
+            _ => {}
+        }
+
+        let data = match self.nav_bar.active_data_mut::<PackageManager>() {
+            Some(data) => data,
+            None => {
+                warn!("No active data found");
+                return Task::none();
             },
+        };
+
+        match msg {
+            PkgManagerMessage::AutoRemoveEdited(s) => data.cmd_auto_remove = s,
+            PkgManagerMessage::CleanEdited(s) => data.cmd_clean = s,
+            PkgManagerMessage::InstallEdited(s) => data.cmd_install = s,
+            PkgManagerMessage::ListEdited(s) => data.cmd_list = s,
+            PkgManagerMessage::PurgeEdited(s) => data.cmd_purge = s,
+            PkgManagerMessage::RemoveEdited(s) => data.cmd_remove = s,
+            PkgManagerMessage::SearchEdited(s) => data.cmd_search = s,
+            PkgManagerMessage::ShowEdited(s) => data.cmd_show = s,
+            PkgManagerMessage::UpdateEdited(s) => data.cmd_update = s,
+            PkgManagerMessage::UpgradeEdited(s) => data.cmd_upgrade = s,
+            PkgManagerMessage::Save => {
+                let pkg_manager = data.clone();
+                self.busy = true;
+
+                return Task::perform(blocking(move || pkg_manager.update()), |res| {
+                    Message::PkgManager(PkgManagerMessage::Saved(res)).into()
+                });
+            }
+            PkgManagerMessage::Reset => {
+                self.select_after_load = Some(data.name.clone());
+                self.error_status = None;
 
-            _ => (),
+                return self.update_items();
+            }
+            PkgManagerMessage::Delete => {
+                let pkg_manager = data.clone();
+
+                return match self.confirm_destructive {
+                    true => self.ask_delete(pkg_manager),
+                    false => self.delete(pkg_manager, false),
+                };
+            }
+            _ => {}
         }
+
+        Task::none()
     }
 }
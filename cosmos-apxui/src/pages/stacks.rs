@@ -1,35 +1,157 @@
-use apx_shim::Stack;
+use apx_shim::{PackageManager, Stack, Subsystem};
 use cosmic::{
     self,
+    app::Task,
     cosmic_theme::{self, Spacing},
     iced::{Alignment, Length},
     iced_widget, theme,
-    widget::{self, button, nav_bar},
+    widget::{self, button, icon, nav_bar, segmented_button::Entity},
     Element,
 };
 use tracing::{debug, warn};
 use crate::app::Message;
-use super::PageModel;
+use crate::config::Config;
+use crate::dialogs::confirm::{ConfirmDialog, ConfirmMessage};
+use crate::dialogs::stack_templates::{GalleryMessage, TemplateGallery};
+use super::{blocking, fill_nav, find_by_name, load, LoadState, PageModel};
+
+/// Marks a nav entry as a stack that hasn't been created in apx yet.
+struct Unsaved;
 
 pub struct StacksModel {
     nav_bar: nav_bar::Model,
+    pkg_manager_names: Vec<String>,
+    package_input: String,
+    search_results: Vec<String>,
+    searching: bool,
+    notice: Option<String>,
+    error_status: Option<String>,
+    busy: bool,
+    state: LoadState,
+    generation: u64,
+    select_after_load: Option<String>,
+    show_built_in: bool,
+    confirm_destructive: bool,
+    /// A delete waiting on the user.
+    confirm: Option<(Stack, ConfirmDialog)>,
+    gallery: Option<TemplateGallery>,
 }
 
 impl StacksModel {
     pub fn new() -> Self {
         Self {
             nav_bar: nav_bar::Model::default(),
+            pkg_manager_names: vec![],
+            package_input: String::new(),
+            search_results: vec![],
+            searching: false,
+            notice: None,
+            error_status: None,
+            busy: false,
+            state: LoadState::default(),
+            generation: 0,
+            select_after_load: None,
+            show_built_in: true,
+            confirm_destructive: true,
+            confirm: None,
+            gallery: None,
         }
     }
+
+    fn is_unsaved(&self, entity: Entity) -> bool {
+        self.nav_bar.data::<Unsaved>(entity).is_some()
+    }
+
+    fn delete(&mut self, stack: Stack, force: bool) -> Task<Message> {
+        self.busy = true;
+
+        Task::perform(blocking(move || stack.remove(force)), |res| {
+            Message::Stack(StackMessage::Deleted(res)).into()
+        })
+    }
+
+    /// Asks before deleting, listing the subsystems built on the stack.
+    fn ask_delete(&mut self, stack: Stack) -> Task<Message> {
+        let name = stack.name.clone();
+
+        let dialog = ConfirmDialog::new(
+            format!("Delete {name}?"),
+            "Delete",
+            &name,
+            "The stack is removed from apx. Subsystems created from it keep running, \
+             but can't be reset anymore.",
+        )
+        .with_impact("Subsystems using this stack")
+        .irreversible();
+
+        self.confirm = Some((stack, dialog));
+
+        let target = name.clone();
+        Task::perform(
+            blocking(move || {
+                Ok(Subsystem::get_all()?
+                    .into_iter()
+                    .filter(|s| s.stack.name == name)
+                    .map(|s| s.name)
+                    .collect())
+            }),
+            move |res| {
+                Message::Stack(StackMessage::Confirm(ConfirmMessage::ImpactLoaded(target, res))).into()
+            },
+        )
+    }
+
+    /// Adds `stack` to the list as a new stack, not yet created in apx, and opens it.
+    fn insert_unsaved(&mut self, stack: Stack) {
+        self.nav_bar
+            .insert()
+            .text("New stack")
+            .data::<Stack>(stack)
+            .data::<Unsaved>(Unsaved)
+            .activate();
+
+        self.clear_editor_state();
+    }
+
+    fn clear_editor_state(&mut self) {
+        self.package_input.clear();
+        self.search_results.clear();
+        self.searching = false;
+        self.notice = None;
+        self.error_status = None;
+    }
 }
+
 #[derive(Debug, Clone)]
 pub enum StackMessage {
+    Loaded(u64, Result<Vec<Stack>, String>),
+    /// Selects the named stack, reloading first if it isn't listed yet.
+    Focus(String),
+    PackageManagersLoaded(Result<Vec<PackageManager>, String>),
+    NameEdited(String),
     BaseEdited(String),
-    PackageManagerEdited(String),
-    //PackagesEdited,
+    PackageManagerSelected(usize),
+    PackageInputEdited(String),
+    AddPackages,
+    RemovePackage(usize),
+    MovePackageUp(usize),
+    MovePackageDown(usize),
+    RemoveDuplicates,
+    Search,
+    SearchResults(Result<Vec<String>, String>),
+    AddSearchResult(String),
+    New,
+    /// Opens the template gallery.
+    Templates,
+    Gallery(GalleryMessage),
     Reset,
     Save,
+    Saved(String, Result<(), String>),
     Delete,
+    Deleted(Result<(), String>),
+    Confirm(ConfirmMessage),
+    CloseNotice,
+    CloseError,
 }
 
 impl Into<Message> for StackMessage {
@@ -38,30 +160,196 @@ impl Into<Message> for StackMessage {
     }
 }
 
+/// Splits pasted text into package names, accepting whitespace, comma or newline separated lists.
+fn split_packages(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|p| !p.is_empty())
+        .map(|p| p.to_string())
+        .collect()
+}
+
+/// Adds packages to a list, returning the ones skipped as duplicates.
+fn add_packages(packages: &mut Vec<String>, new: Vec<String>) -> Vec<String> {
+    let mut duplicates = vec![];
+
+    for package in new {
+        if packages.contains(&package) {
+            if !duplicates.contains(&package) {
+                duplicates.push(package);
+            }
+        } else {
+            packages.push(package);
+        }
+    }
+
+    duplicates
+}
+
+fn is_duplicate(packages: &[String], index: usize) -> bool {
+    packages[..index].contains(&packages[index])
+}
+
+impl StacksModel {
+    fn package_editor<'a>(&'a self, data: &'a Stack) -> Element<'a, Message> {
+        let spacing = Spacing::default();
+        let mut list = widget::Column::new().spacing(spacing.space_xxs);
+
+        if data.packages.is_empty() {
+            list = list.push(widget::text::body("No packages"));
+        }
+
+        let last = data.packages.len().saturating_sub(1);
+
+        for (i, package) in data.packages.iter().enumerate() {
+            let mut row = widget::Row::new()
+                .spacing(spacing.space_xxs)
+                .align_y(Alignment::Center)
+                .push(widget::text::body(package.as_str()).width(Length::Fill));
+
+            if is_duplicate(&data.packages, i) {
+                row = row.push(widget::text::caption("Duplicate"));
+            }
+
+            if !data.built_in {
+                let mut up = button::icon(icon::from_name("go-up-symbolic"));
+                if i > 0 {
+                    up = up.on_press(StackMessage::MovePackageUp(i).into());
+                }
+
+                let mut down = button::icon(icon::from_name("go-down-symbolic"));
+                if i < last {
+                    down = down.on_press(StackMessage::MovePackageDown(i).into());
+                }
+
+                row = row.push(up).push(down).push(
+                    button::icon(icon::from_name("edit-delete-symbolic"))
+                        .on_press(StackMessage::RemovePackage(i).into()),
+                );
+            }
+
+            list = list.push(row);
+        }
+
+        if data.built_in {
+            return list.into();
+        }
+
+        let has_duplicates = (0..data.packages.len()).any(|i| is_duplicate(&data.packages, i));
+
+        let mut search = button::standard("Search");
+        if !self.searching && !self.package_input.trim().is_empty() {
+            search = search.on_press(StackMessage::Search.into());
+        }
+
+        let mut column = widget::Column::new()
+            .spacing(spacing.space_s)
+            .push(
+                widget::Row::new()
+                    .spacing(spacing.space_xs)
+                    .align_y(Alignment::End)
+                    .push(
+                        widget::TextInput::new("package-name, or paste a list", &self.package_input)
+                            .label("Add packages")
+                            .on_input(|text| StackMessage::PackageInputEdited(text).into()),
+                    )
+                    .push(button::suggested("Add").on_press(StackMessage::AddPackages.into()))
+                    .push(search),
+            );
+
+        if let Some(notice) = &self.notice {
+            column = column.push(cosmos_common::info(notice, StackMessage::CloseNotice.into()));
+        }
+
+        if self.searching {
+            column = column.push(widget::text::caption("Searching…"));
+        }
+
+        if !self.search_results.is_empty() {
+            let mut results = widget::Column::new().spacing(spacing.space_xxs);
+            for result in &self.search_results {
+                results = results.push(
+                    widget::Row::new()
+                        .align_y(Alignment::Center)
+                        .push(widget::text::body(result.as_str()).width(Length::Fill))
+                        .push(
+                            button::text("Add")
+                                .on_press(StackMessage::AddSearchResult(result.clone()).into()),
+                        ),
+                );
+            }
+
+            column = column.push(
+                iced_widget::scrollable(results).height(Length::Fixed(160.)),
+            );
+        }
+
+        column = column.push(list);
+
+        if has_duplicates {
+            column = column.push(
+                button::text("Remove duplicates").on_press(StackMessage::RemoveDuplicates.into()),
+            );
+        }
+
+        column.into()
+    }
+}
+
 impl PageModel for StacksModel {
     fn view(&self) -> cosmic::Element<'_, Message> {
         let data = self.nav_bar.active_data::<Stack>();
 
         if let Some(data) = data {
-            let editors: Vec<cosmic::Element<'_, Message>> = match data.built_in {
-                false => vec![
-                    widget::TextInput::new("base:latest", &data.base)
-                        .label("Base")
-                        .on_input(|text| StackMessage::BaseEdited(text).into())
-                        .into(),
-                    widget::TextInput::new("pkg manager", &data.package_manager)
-                        .label("Package Manager")
-                        .on_input(|text| StackMessage::PackageManagerEdited(text).into())
-                        .into(),
-                ],
-                true => vec![
-                    widget::TextInput::new("base:latest", &data.base)
-                        .label("Base")
-                        .into(),
-                    widget::TextInput::new("pkg manager", &data.package_manager)
-                        .label("Package Manager")
+            let unsaved = self.is_unsaved(self.nav_bar.active());
+
+            let selected_pm = self
+                .pkg_manager_names
+                .iter()
+                .position(|p| p == &data.package_manager);
+
+            let mut editors: Vec<cosmic::Element<'_, Message>> = vec![];
+
+            if unsaved {
+                editors.push(
+                    widget::TextInput::new("my-stack", &data.name)
+                        .label("Name")
+                        .on_input(|text| StackMessage::NameEdited(text).into())
                         .into(),
-                ],
+                );
+            }
+
+            match data.built_in {
+                false => {
+                    editors.push(
+                        widget::TextInput::new("base:latest", &data.base)
+                            .label("Base")
+                            .on_input(|text| StackMessage::BaseEdited(text).into())
+                            .into(),
+                    );
+                    editors.push(
+                        widget::Column::new()
+                            .spacing(4)
+                            .push(widget::text::caption("Package Manager"))
+                            .push(widget::dropdown(
+                                &self.pkg_manager_names,
+                                selected_pm,
+                                |i| StackMessage::PackageManagerSelected(i).into(),
+                            ))
+                            .into(),
+                    );
+                }
+                true => {
+                    editors.push(
+                        widget::TextInput::new("base:latest", &data.base)
+                            .label("Base")
+                            .into(),
+                    );
+                    editors.push(
+                        widget::TextInput::new("pkg manager", &data.package_manager)
+                            .label("Package Manager")
+                            .into(),
+                    );
+                }
             };
 
             let mut column = widget::Column::new();
@@ -70,34 +358,66 @@ impl PageModel for StacksModel {
                 column = column.push(element); // Reassign the column
             }
 
-            iced_widget::column![
-                iced_widget::row![
-                    widget::Text::new(&data.name).size(24).width(Length::Fill),
-                    iced_widget::row![
-                        button::link("Reset").on_press(StackMessage::Reset.into()),
-                        button::link("Save").on_press(StackMessage::Save.into()),
-                        button::link("Delete").on_press(StackMessage::Delete.into()),
-                    ]
+            let title = match data.name.is_empty() {
+                true => "New stack",
+                false => data.name.as_str(),
+            };
+
+            let mut actions = iced_widget::row![
+                button::link("Reset").on_press(StackMessage::Reset.into()),
+            ];
+
+            if !data.built_in {
+                let mut save = button::link("Save");
+                if !self.busy {
+                    save = save.on_press(StackMessage::Save.into());
+                }
+                actions = actions.push(save);
+            }
+
+            if !unsaved {
+                let mut delete = button::link("Delete");
+                if !self.busy {
+                    delete = delete.on_press(StackMessage::Delete.into());
+                }
+                actions = actions.push(delete);
+            }
+
+            let mut content = iced_widget::column![iced_widget::row![
+                widget::Text::new(title).size(24).width(Length::Fill),
+                actions
                     .spacing(20)
                     .width(Length::Shrink)
                     .align_y(Alignment::Center)
-                ]
-                .padding([0, 0, 20, 0])
-                .height(Length::Shrink),
-                iced_widget::scrollable(
-                    iced_widget::column![
-                        widget::Text::new("Commands").size(18),
-                        widget::Container::new(column.spacing(20).padding(20))
+            ]
+            .padding([0, 0, 20, 0])
+            .height(Length::Shrink)];
+
+            if let Some(error) = &self.error_status {
+                content = content.push(cosmos_common::error(error, StackMessage::CloseError.into()));
+            }
+
+            content
+                .push(
+                    iced_widget::scrollable(
+                        iced_widget::column![
+                            widget::Text::new("Commands").size(18),
+                            widget::Container::new(column.spacing(20).padding(20))
+                                .style(|_| theme::Container::primary(&cosmic_theme::Theme::default())),
+                            widget::Text::new("Packages").size(18),
+                            widget::Container::new(
+                                widget::Container::new(self.package_editor(data)).padding(20)
+                            )
                             .style(|_| theme::Container::primary(&cosmic_theme::Theme::default())),
-                    ]
-                    .spacing(Spacing::default().space_xs)
+                        ]
+                        .spacing(Spacing::default().space_xs)
+                    )
+                    .height(Length::Fill),
                 )
-                .height(Length::Fill),
-            ]
-            .into()
+                .into()
         } else {
             widget::Column::new()
-                .push(widget::Text::new("No package manager selected").size(24))
+                .push(widget::Text::new("No stack selected").size(24))
                 .into()
         }
     }
@@ -106,81 +426,331 @@ impl PageModel for StacksModel {
         &self.nav_bar
     }
 
-    fn update_items(&mut self) {
-        let data = apx_shim::Stack::get_all();
+    fn load_state(&self) -> &LoadState {
+        &self.state
+    }
 
-        self.nav_bar = match data {
-            Ok(data) => {
-                let mut items = nav_bar::Model::default();
-                for item in data {
-                    items.insert().text(item.name.clone()).data::<Stack>(item);
-                }
-                items
-            }
-            Err(_) => nav_bar::Model::default(),
-        };
+    fn update_items(&mut self) -> Task<Message> {
+        if self.select_after_load.is_none() && !self.is_unsaved(self.nav_bar.active()) {
+            self.select_after_load = self.nav_bar.active_data::<Stack>().map(|s| s.name.clone());
+        }
+
+        self.generation += 1;
+        self.state = LoadState::Loading;
+
+        let stacks = load(self.generation, apx_shim::Stack::get_all, |generation, res| {
+            Message::Stack(StackMessage::Loaded(generation, res))
+        });
+        let pkg_managers = Task::perform(blocking(PackageManager::get_all), |res| {
+            Message::Stack(StackMessage::PackageManagersLoaded(res)).into()
+        });
+
+        Task::batch([stacks, pkg_managers])
     }
 
-    fn on_select(&mut self, item: widget::segmented_button::Entity) {
+    fn on_select(&mut self, item: widget::segmented_button::Entity) -> Task<Message> {
         self.nav_bar.activate(item);
+        self.clear_editor_state();
+
+        Task::none()
     }
 
-    fn on_message(&mut self, message: Message) {
+    fn dialog(&self) -> Option<cosmic::Element<'_, Message>> {
+        if let Some(gallery) = &self.gallery {
+            return Some(gallery.view());
+        }
+
+        self.confirm
+            .as_ref()
+            .map(|(_, dialog)| dialog.view(|m| StackMessage::Confirm(m).into()))
+    }
+
+    fn apply_config(&mut self, config: &Config) -> Task<Message> {
+        self.confirm_destructive = config.confirm_destructive;
+
+        if self.show_built_in == config.show_built_in_stacks {
+            return Task::none();
+        }
+
+        self.show_built_in = config.show_built_in_stacks;
+        self.update_items()
+    }
+
+    fn on_message(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Stack(StackMessage::Loaded(generation, res)) => {
+                //A newer load has been started since, so this result is stale.
+                if generation != self.generation {
+                    return Task::none();
+                }
+
+                match res {
+                    Ok(mut data) => {
+                        if !self.show_built_in {
+                            data.retain(|s| !s.built_in);
+                        }
+
+                        let select = self.select_after_load.take();
+                        self.nav_bar = fill_nav(data, |s: &Stack| s.name.clone(), select.as_deref());
+                        self.state = LoadState::Loaded;
+                    }
+                    Err(e) => self.state = LoadState::Failed(e),
+                }
+
+                return Task::none();
+            }
+            Message::Stack(StackMessage::Templates) => {
+                self.gallery = Some(TemplateGallery::new());
+                return Task::none();
+            }
+            Message::Stack(StackMessage::Gallery(GalleryMessage::Cancel)) => {
+                self.gallery = None;
+                return Task::none();
+            }
+            Message::Stack(StackMessage::Gallery(GalleryMessage::Use)) => {
+                if let Some(gallery) = self.gallery.take() {
+                    self.insert_unsaved(gallery.stack());
+                }
+
+                return Task::none();
+            }
+            Message::Stack(StackMessage::Gallery(msg)) => {
+                if let Some(gallery) = self.gallery.as_mut() {
+                    gallery.update(msg);
+                }
+
+                return Task::none();
+            }
+            Message::Stack(StackMessage::Focus(name)) => {
+                return match find_by_name(&self.nav_bar, |s: &Stack| &s.name, &name) {
+                    Some(entity) => self.on_select(entity),
+                    None => {
+                        self.select_after_load = Some(name);
+                        self.update_items()
+                    }
+                };
+            }
+            Message::Stack(StackMessage::PackageManagersLoaded(res)) => {
+                self.pkg_manager_names = match res {
+                    Ok(pms) => pms.into_iter().map(|p| p.name).collect(),
+                    Err(e) => {
+                        warn!("Could not load package managers: {e}");
+                        vec![]
+                    }
+                };
+
+                return Task::none();
+            }
+            Message::Stack(StackMessage::Saved(name, res)) => {
+                self.busy = false;
+
+                return match res {
+                    Ok(_) => {
+                        self.error_status = None;
+                        self.select_after_load = Some(name);
+                        self.update_items()
+                    }
+                    Err(e) => {
+                        self.error_status = Some(format!("Error on saving: {e}"));
+                        Task::none()
+                    }
+                };
+            }
+            Message::Stack(StackMessage::Confirm(ConfirmMessage::Cancel)) => {
+                self.confirm = None;
+                return Task::none();
+            }
+            Message::Stack(StackMessage::Confirm(msg)) => {
+                let confirmed = match self.confirm.as_mut() {
+                    Some((_, dialog)) => dialog.update(msg),
+                    None => false,
+                };
+
+                if confirmed {
+                    if let Some((stack, dialog)) = self.confirm.take() {
+                        return self.delete(stack, dialog.force());
+                    }
+                }
+
+                return Task::none();
+            }
+            Message::Stack(StackMessage::Deleted(res)) => {
+                self.busy = false;
+
+                match res {
+                    Ok(_) => debug!("Successfully deleted"),
+                    Err(e) => self.error_status = Some(format!("Error on delete: {e}")),
+                }
+
+                return self.update_items();
+            }
+            _ => {}
+        }
+
+        if let Message::Stack(StackMessage::New) = message {
+            let package_manager = self.pkg_manager_names.first().cloned().unwrap_or_default();
+
+            self.insert_unsaved(Stack {
+                name: String::new(),
+                base: String::new(),
+                packages: vec![],
+                package_manager,
+                built_in: false,
+            });
+            return Task::none();
+        }
+
+        let active = self.nav_bar.active();
+        let unsaved = self.is_unsaved(active);
 
         let data = match self.nav_bar.active_data_mut::<Stack>() {
             Some(data) => data,
             None => {
                 warn!("No active data found");
-                return;
+                return Task::none();
             },
         };
 
         match message {
             Message::Stack(msg) => match msg {
+                StackMessage::NameEdited(text) => {
+                    data.name = text;
+                }
                 StackMessage::BaseEdited(text) => {
                     data.base = text;
                 }
-                StackMessage::PackageManagerEdited(text) => {
-                    data.package_manager = text;
+                StackMessage::PackageManagerSelected(i) => {
+                    if let Some(name) = self.pkg_manager_names.get(i) {
+                        data.package_manager = name.clone();
+                    }
                 }
-                //StackMessage::PackagesEdited => {}
-                StackMessage::Save => match data.update() {
-                    Ok(_) => return,
-                    Err(e) => todo!("Handle error on saving: {e}"),
-                },
-                StackMessage::Reset => {
+                StackMessage::PackageInputEdited(text) => {
+                    self.package_input = text;
+                }
+                StackMessage::AddPackages => {
+                    let duplicates = add_packages(&mut data.packages, split_packages(&self.package_input));
 
-                    let name = match self
-                    .nav_bar
-                    .active_data_mut::<Stack>() {
-                        Some(data) => data.name.clone(),
-                        None => {
-                            warn!("No active data found");
-                            return;
-                        },
+                    self.package_input.clear();
+                    self.notice = match duplicates.is_empty() {
+                        true => None,
+                        false => Some(format!("Already in the stack: {}", duplicates.join(", "))),
                     };
+                }
+                StackMessage::AddSearchResult(package) => {
+                    let duplicates = add_packages(&mut data.packages, vec![package]);
 
-                    self.update_items();
-                    let matched = self
-                        .nav_bar
-                        .iter()
-                        .find(|e| self.nav_bar.data::<Stack>(*e).unwrap().name == name);
+                    self.notice = match duplicates.is_empty() {
+                        true => None,
+                        false => Some(format!("Already in the stack: {}", duplicates.join(", "))),
+                    };
+                }
+                StackMessage::RemovePackage(i) => {
+                    if i < data.packages.len() {
+                        data.packages.remove(i);
+                    }
+                }
+                StackMessage::MovePackageUp(i) => {
+                    if i > 0 && i < data.packages.len() {
+                        data.packages.swap(i, i - 1);
+                    }
+                }
+                StackMessage::MovePackageDown(i) => {
+                    if i + 1 < data.packages.len() {
+                        data.packages.swap(i, i + 1);
+                    }
+                }
+                StackMessage::RemoveDuplicates => {
+                    let mut seen: Vec<String> = vec![];
+                    data.packages.retain(|p| {
+                        if seen.contains(p) {
+                            false
+                        } else {
+                            seen.push(p.clone());
+                            true
+                        }
+                    });
+                }
+                StackMessage::Search => {
+                    let query = self.package_input.trim().to_string();
+                    let package_manager = data.package_manager.clone();
 
-                    match matched {
-                        Some(m) => self.nav_bar.activate(m),
-                        None => todo!("Handle no match on reset"),
+                    self.searching = true;
+                    self.search_results.clear();
+
+                    return Task::perform(
+                        blocking(move || {
+                            let subsystem = Subsystem::get_all()?
+                                .into_iter()
+                                .find(|s| s.stack.package_manager == package_manager);
+
+                            match subsystem {
+                                Some(s) => s.search(&query),
+                                None => Err(anyhow::anyhow!(
+                                    "Searching needs a subsystem that uses {package_manager}"
+                                )),
+                            }
+                        }),
+                        |res| Message::Stack(StackMessage::SearchResults(res)).into(),
+                    );
+                }
+                StackMessage::SearchResults(res) => {
+                    self.searching = false;
+                    match res {
+                        Ok(results) => {
+                            if results.is_empty() {
+                                self.notice = Some("No packages found".into());
+                            }
+                            self.search_results = results;
+                        }
+                        Err(e) => self.error_status = Some(format!("Search failed: {e}")),
                     }
                 }
-                StackMessage::Delete => match data.remove(true) {
-                    Ok(_) => {
-                        debug!("Successfully deleted");
-                        self.nav_bar.remove(self.nav_bar.active());
+                StackMessage::Save => {
+                    if data.name.trim().is_empty() || data.base.trim().is_empty() {
+                        self.error_status = Some("A stack needs a name and a base".into());
+                        return Task::none();
+                    }
+
+                    let mut stack = data.clone();
+                    let name = stack.name.clone();
+                    self.busy = true;
+
+                    return Task::perform(
+                        blocking(move || match unsaved {
+                            true => stack.create(),
+                            false => stack.update(),
+                        }),
+                        move |res| Message::Stack(StackMessage::Saved(name, res)).into(),
+                    );
+                }
+                StackMessage::Reset => {
+                    if unsaved {
+                        self.nav_bar.remove(active);
+                        self.clear_editor_state();
+                        return Task::none();
                     }
-                    Err(_) => todo!(),
-                },
+
+                    self.select_after_load = Some(data.name.clone());
+                    self.clear_editor_state();
+
+                    return self.update_items();
+                }
+                StackMessage::Delete => {
+                    let stack = data.clone();
+
+                    return match self.confirm_destructive {
+                        true => self.ask_delete(stack),
+                        false => self.delete(stack, false),
+                    };
+                }
+                StackMessage::CloseNotice => self.notice = None,
+                StackMessage::CloseError => self.error_status = None,
+                _ => {}
             },
 
             _ => {}
         }
+
+        Task::none()
     }
 }
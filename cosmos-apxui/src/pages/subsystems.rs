@@ -1,13 +1,18 @@
-use super::PageModel;
+use super::{blocking, fill_nav, find_by_name, load, LoadState, PageModel};
 use crate::app::Message;
-use apx_shim::Subsystem;
+use crate::config::Config;
+use crate::dialogs::confirm::{ConfirmDialog, ConfirmMessage};
+use crate::dialogs::create_subsystem::{CreateSubsystemWizard, WizardMessage};
+use crate::notifications::{self, Notice};
+use apx_shim::{DesktopApp, ExportedBinary, Subsystem};
 use cosmic::{
     self,
+    app::Task,
     cosmic_theme::{self, Spacing},
-    iced::Length,
+    iced::{Alignment, Length},
     iced_widget, theme,
     widget::{
-        self, nav_bar,
+        self, button, icon, nav_bar,
         segmented_button::{self, Entity, SingleSelect, VerticalSegmentedButton},
     },
 };
@@ -20,7 +25,18 @@ pub struct SubSystemsModel {
     destructive_actions: segmented_button::Model<SingleSelect>,
     error_status: Option<String>,
     action_status: Option<String>,
-    dialog_string: Option<String>,
+    wizard: Option<CreateSubsystemWizard>,
+    state: LoadState,
+    generation: u64,
+    select_after_load: Option<String>,
+    apps: Option<Vec<DesktopApp>>,
+    binaries: Vec<ExportedBinary>,
+    apps_loading: bool,
+    binary_input: String,
+    default_stack: String,
+    confirm_destructive: bool,
+    /// A reset or delete waiting on the user, with the action to run once confirmed.
+    confirm: Option<(SubsystemMessage, ConfirmDialog)>,
 }
 
 impl SubSystemsModel {
@@ -59,15 +75,274 @@ impl SubSystemsModel {
             nav_bar: nav_bar::Model::default(),
             error_status: None,
             action_status: None,
-            dialog_string: None,
+            wizard: None,
+            state: LoadState::default(),
+            generation: 0,
+            select_after_load: None,
+            apps: None,
+            binaries: vec![],
+            apps_loading: false,
+            binary_input: String::new(),
+            default_stack: String::new(),
+            confirm_destructive: true,
+            confirm: None,
             sub_actions,
             destructive_actions,
         }
     }
+
+    /// Opens the create wizard, starting from `stack` or the default one.
+    fn open_wizard(&mut self, stack: Option<String>) -> Task<Message> {
+        let existing = self
+            .nav_bar
+            .iter()
+            .filter_map(|e| self.nav_bar.data::<Subsystem>(e))
+            .map(|s| s.name.clone())
+            .collect();
+
+        let stack = stack.unwrap_or_else(|| self.default_stack.clone());
+        let (wizard, task) = CreateSubsystemWizard::open(existing, &stack);
+        self.wizard = Some(wizard);
+        task
+    }
+
+    /// Runs an action against the selected subsystem in the background, then reloads.
+    fn run_action(
+        &mut self,
+        label: &'static str,
+        action: impl FnOnce(Subsystem) -> anyhow::Result<()> + Send + 'static,
+    ) -> Task<Message> {
+        let subsystem = match self.nav_bar.active_data::<Subsystem>() {
+            Some(s) => s.clone(),
+            None => {
+                warn!("No active data found");
+                return Task::none();
+            }
+        };
+
+        self.action_status = Some(format!("Running {label} on {}…", subsystem.name));
+
+        let name = subsystem.name.clone();
+        Task::perform(blocking(move || action(subsystem)), move |res| {
+            Message::Subsystem(SubsystemMessage::ActionFinished(label, name, res)).into()
+        })
+    }
+
+    fn load_apps(&mut self) -> Task<Message> {
+        let subsystem = match self.nav_bar.active_data::<Subsystem>() {
+            Some(s) => s.clone(),
+            None => return Task::none(),
+        };
+
+        self.apps_loading = true;
+
+        Task::perform(
+            async move {
+                let name = subsystem.name.clone();
+                let res = blocking(move || {
+                    let apps = subsystem.list_apps()?;
+                    Ok((apps, subsystem.list_exported_binaries()))
+                });
+
+                (name, res.await)
+            },
+            |(name, res)| Message::Subsystem(SubsystemMessage::AppsLoaded(name, res)).into(),
+        )
+    }
+
+    /// Runs an export action against the selected subsystem, then reloads its apps.
+    fn run_app_action(
+        &mut self,
+        label: &'static str,
+        action: impl FnOnce(Subsystem) -> anyhow::Result<()> + Send + 'static,
+    ) -> Task<Message> {
+        let subsystem = match self.nav_bar.active_data::<Subsystem>() {
+            Some(s) => s.clone(),
+            None => return Task::none(),
+        };
+
+        self.apps_loading = true;
+
+        Task::perform(blocking(move || action(subsystem)), move |res| {
+            Message::Subsystem(SubsystemMessage::AppActionFinished(label, res)).into()
+        })
+    }
+
+    fn apps_view(&self, data: &Subsystem) -> cosmic::Element<'_, Message> {
+        let spacing = Spacing::default();
+        let mut column = widget::Column::new().spacing(spacing.space_s);
+
+        match &self.apps {
+            None if self.apps_loading => {
+                column = column.push(widget::text::body("Loading applications…"));
+            }
+            None => {
+                let hint = match data.running() {
+                    true => "Applications haven't been loaded yet.",
+                    false => "Listing applications starts the subsystem.",
+                };
+
+                column = column.push(widget::text::body(hint)).push(
+                    button::standard("Load applications").on_press(SubsystemMessage::LoadApps.into()),
+                );
+            }
+            Some(apps) => {
+                if apps.is_empty() {
+                    column = column.push(widget::text::body("No applications with a desktop entry"));
+                }
+
+                for app in apps {
+                    let mut export = widget::toggler(app.exported).label("Exported");
+                    let mut launch = button::text("Launch");
+
+                    if !self.apps_loading {
+                        let toggled = app.clone();
+                        export = export.on_toggle(move |v| {
+                            SubsystemMessage::ToggleExport(toggled.clone(), v).into()
+                        });
+                        launch = launch.on_press(SubsystemMessage::Launch(app.clone()).into());
+                    }
+
+                    column = column.push(
+                        widget::Row::new()
+                            .spacing(spacing.space_s)
+                            .align_y(Alignment::Center)
+                            .push(
+                                widget::Column::new()
+                                    .push(widget::text::body(app.name.as_str()))
+                                    .push(widget::text::caption(app.id.as_str()))
+                                    .width(Length::Fill),
+                            )
+                            .push(launch)
+                            .push(export),
+                    );
+                }
+            }
+        }
+
+        if !self.binaries.is_empty() {
+            column = column.push(widget::text::heading("Exported binaries"));
+        }
+
+        for binary in &self.binaries {
+            let mut export = widget::toggler(true).label("Exported");
+
+            if !self.apps_loading {
+                let path = binary.path.clone();
+                export = export.on_toggle(move |_| SubsystemMessage::UnexportBinary(path.clone()).into());
+            }
+
+            column = column.push(
+                widget::Row::new()
+                    .spacing(spacing.space_s)
+                    .align_y(Alignment::Center)
+                    .push(
+                        widget::Column::new()
+                            .push(widget::text::body(binary.name.as_str()))
+                            .push(widget::text::caption(binary.path.as_str()))
+                            .width(Length::Fill),
+                    )
+                    .push(export),
+            );
+        }
+
+        let mut export_bin = button::standard("Export");
+        let mut unexport_bin = button::standard("Unexport");
+        if !self.apps_loading && !self.binary_input.trim().is_empty() {
+            let binary = self.binary_input.trim().to_string();
+            export_bin = export_bin.on_press(SubsystemMessage::ExportBinary.into());
+            unexport_bin = unexport_bin.on_press(SubsystemMessage::UnexportBinary(binary).into());
+        }
+
+        column
+            .push(
+                widget::Row::new()
+                    .spacing(spacing.space_xs)
+                    .align_y(Alignment::End)
+                    .push(
+                        widget::text_input("binary-name", &self.binary_input)
+                            .label("Binary")
+                            .on_input(|t| SubsystemMessage::BinaryInputEdited(t).into()),
+                    )
+                    .push(export_bin)
+                    .push(unexport_bin),
+            )
+            .into()
+    }
+
+    fn handle_action(&mut self, action: SubsystemMessage) -> Task<Message> {
+        match action {
+            SubsystemMessage::Start => self.run_action("start", |s| s.start()),
+            SubsystemMessage::Stop => self.run_action("stop", |s| s.stop()),
+            SubsystemMessage::Autoremove => self.run_action("autoremove", |s| s.autoremove()),
+            SubsystemMessage::CleanPackageManagerCache => {
+                self.run_action("clean", |s| s.clean())
+            }
+            SubsystemMessage::Reset | SubsystemMessage::Delete if self.confirm_destructive => {
+                self.ask_confirmation(action)
+            }
+            SubsystemMessage::Reset => self.run_action("reset", |s| s.reset(false)),
+            SubsystemMessage::Delete => self.run_action("delete", |s| s.remove(false)),
+            _ => Task::none(),
+        }
+    }
+
+    /// Asks before a reset or delete. Deleting lists what was exported from the subsystem,
+    /// read from the host since the apps may not have been loaded.
+    fn ask_confirmation(&mut self, action: SubsystemMessage) -> Task<Message> {
+        let subsystem = match self.nav_bar.active_data::<Subsystem>() {
+            Some(s) => s.clone(),
+            None => return Task::none(),
+        };
+        let name = subsystem.name.clone();
+
+        let dialog = match action {
+            SubsystemMessage::Reset => ConfirmDialog::new(
+                format!("Reset {name}?"),
+                "Reset",
+                &name,
+                "The subsystem is recreated from its stack. Packages installed since and \
+                 changes made outside the home directory are lost.",
+            ),
+            _ => ConfirmDialog::new(
+                format!("Delete {name}?"),
+                "Delete",
+                &name,
+                "The subsystem's container is removed, along with everything installed in it.",
+            )
+            .with_impact("Exported applications and binaries that stop working"),
+        };
+
+        let load_impact = matches!(action, SubsystemMessage::Delete);
+        self.confirm = Some((action, dialog.irreversible()));
+
+        if !load_impact {
+            return Task::none();
+        }
+
+        Task::perform(
+            blocking(move || {
+                let mut exported = subsystem.list_exported_apps();
+                exported.extend(subsystem.list_exported_binaries().into_iter().map(|b| b.name));
+                Ok(exported)
+            }),
+            move |res| {
+                Message::Subsystem(SubsystemMessage::Confirm(ConfirmMessage::ImpactLoaded(
+                    name, res,
+                )))
+                .into()
+            },
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum SubsystemMessage {
+    Loaded(u64, Result<Vec<Subsystem>, String>),
+    /// Selects the named subsystem, reloading first if it isn't listed yet.
+    Focus(String),
+    /// Opens the create wizard, preselecting the given stack over the default one.
+    NewWithStack(Option<String>),
     Reset,
     Start,
     Stop,
@@ -76,8 +351,21 @@ pub enum SubsystemMessage {
     Delete,
     HandleSubButton(Entity),
     HandleDestButton(Entity),
+    ActionFinished(&'static str, String, Result<(), String>),
+    TerminalOpened(Result<(), String>),
+    Confirm(ConfirmMessage),
+    LoadApps,
+    AppsLoaded(String, Result<(Vec<DesktopApp>, Vec<ExportedBinary>), String>),
+    ToggleExport(DesktopApp, bool),
+    Launch(DesktopApp),
+    BinaryInputEdited(String),
+    ExportBinary,
+    /// Unexports the binary at the given path inside the subsystem.
+    UnexportBinary(String),
+    AppActionFinished(&'static str, Result<(), String>),
     CloseError,
-    CloseAction,
+    New,
+    Wizard(WizardMessage),
 }
 
 impl Into<Message> for SubsystemMessage {
@@ -103,45 +391,44 @@ impl PageModel for SubSystemsModel {
         let mut content = Vec::new();
 
         if data.is_none() {
-            return widget::Column::new()
-                .push(widget::Text::new("No subsystem selected").size(24))
-                .into();
+            let mut column =
+                widget::Column::new().push(widget::Text::new("No subsystem selected").size(24));
+
+            if let Some(error) = &self.error_status {
+                column = column.push(cosmos_common::error(error, SubsystemMessage::CloseError.into()));
+            }
+
+            return column.into();
         }
 
         let data = data.unwrap();
 
         content.push(
-            widget::Text::new(&data.name)
-                .size(24)
-                .width(Length::Fill)
-                .into(),
+            iced_widget::row![
+                widget::Text::new(&data.name).size(24).width(Length::Fill),
+                widget::button::suggested("Open terminal")
+                    .on_press(Message::OpenTerminal(data.name.clone())),
+            ]
+            .align_y(Alignment::Center)
+            .padding([0, 0, 20, 0])
+            .into(),
         );
 
         if let Some(error) = &self.error_status {
             content.push(cosmos_common::error(error, SubsystemMessage::CloseError.into()).into());
         }
 
-        if let Some(string) = &self.dialog_string {
+        if let Some(action) = &self.action_status {
             content.push(
-                widget::Popover::new(
-                    widget::dialog()
-                        .title("Dialog")
-                        .body(string)
-                        .primary_action(
-                            widget::button::destructive("Ok")
-                                .on_press(SubsystemMessage::CloseAction.into()),
-                        ),
-                )
-                .modal(true)
-                .position(widget::popover::Position::Center)
+                iced_widget::row![
+                    icon::from_name("process-working-symbolic").size(16).icon(),
+                    widget::text::body(action.as_str()),
+                ]
+                .spacing(Spacing::default().space_xs)
                 .into(),
             );
         }
 
-        // if let Some(action) = &self.action_status {
-        //     content.push(widget::toaster(&self.toasts,widget::text(action)).into());
-        // }
-
         content.push(
             iced_widget::column![iced_widget::scrollable(
                 iced_widget::column![
@@ -151,13 +438,18 @@ impl PageModel for SubSystemsModel {
                             labelled_info("Status", &data.status),
                             labelled_info("Stack", &data.stack.name),
                             labelled_info("Package Manager", &data.stack.package_manager),
-                            //TODO: Exported programs
                         ]
                         .spacing(20)
                         .padding(20)
                     )
                     .style(|_| theme::Container::primary(&cosmic_theme::Theme::default()))
                     .width(Length::Fill),
+                    widget::Text::new("Applications").size(18),
+                    widget::Container::new(
+                        widget::Container::new(self.apps_view(data)).padding(20)
+                    )
+                    .style(|_| theme::Container::primary(&cosmic_theme::Theme::default()))
+                    .width(Length::Fill),
                     widget::Text::new("Subsystem actions").size(18),
                     widget::Container::new(
                         VerticalSegmentedButton::new(&self.sub_actions)
@@ -193,139 +485,226 @@ impl PageModel for SubSystemsModel {
         iced_widget::column(content).into()
     }
 
+    fn dialog(&self) -> Option<cosmic::Element<'_, Message>> {
+        if let Some((_, confirm)) = &self.confirm {
+            return Some(confirm.view(|m| SubsystemMessage::Confirm(m).into()));
+        }
+
+        self.wizard.as_ref().map(|w| w.view())
+    }
+
     fn current_items(&self) -> &nav_bar::Model {
         &self.nav_bar
     }
 
-    fn on_select(&mut self, item: widget::segmented_button::Entity) {
+    fn load_state(&self) -> &LoadState {
+        &self.state
+    }
+
+    fn on_select(&mut self, item: widget::segmented_button::Entity) -> Task<Message> {
         self.nav_bar.activate(item);
+        self.apps = None;
+        self.binaries.clear();
+        self.apps_loading = false;
+        self.binary_input.clear();
+
+        //Listing apps would start a stopped subsystem, so leave that to the user.
+        match self.nav_bar.active_data::<Subsystem>() {
+            Some(s) if s.running() => self.load_apps(),
+            _ => Task::none(),
+        }
     }
 
-    fn update_items(&mut self) {
-        let data = apx_shim::Subsystem::get_all();
-
-        self.nav_bar = match data {
-            Ok(data) => {
-                let mut items = nav_bar::Model::default();
-                for item in data {
-                    items
-                        .insert()
-                        .text(item.name.clone())
-                        .data::<Subsystem>(item);
-                }
-                items
-            }
-            Err(_) => nav_bar::Model::default(),
-        };
+    fn update_items(&mut self) -> Task<Message> {
+        if self.select_after_load.is_none() {
+            self.select_after_load = self.nav_bar.active_data::<Subsystem>().map(|s| s.name.clone());
+        }
+
+        self.generation += 1;
+        self.state = LoadState::Loading;
+
+        load(self.generation, apx_shim::Subsystem::get_all, |generation, res| {
+            Message::Subsystem(SubsystemMessage::Loaded(generation, res))
+        })
     }
 
-    fn on_message(&mut self, message: Message) {
-        let data = match self.nav_bar.active_data_mut::<Subsystem>() {
-            Some(data) => data,
-            None => {
-                warn!("No active data found");
-                return;
-            }
+    fn apply_config(&mut self, config: &Config) -> Task<Message> {
+        self.default_stack = config.default_stack.clone();
+        self.confirm_destructive = config.confirm_destructive;
+
+        Task::none()
+    }
+
+    fn on_message(&mut self, message: Message) -> Task<Message> {
+        let msg = match message {
+            Message::Subsystem(msg) => msg,
+            _ => return Task::none(),
         };
 
-        self.error_status = Some(format!("Error on reset: some test error"));
-
-        match message {
-            Message::Subsystem(msg) => match msg {
-                SubsystemMessage::HandleDestButton(e) => {
-                    match self
-                        .destructive_actions
-                        .data::<SubsystemMessage>(e)
-                        .unwrap()
-                    {
-                        SubsystemMessage::Reset => match data.reset(true) {
-                            Ok(_) => {}
-                            Err(e) => {
-                                self.error_status = Some(format!("Error on reset: {e}"));
-                            }
-                        },
-                        SubsystemMessage::Delete => match data.remove(true) {
-                            Ok(_) => {}
-                            Err(e) => {
-                                self.error_status = Some(format!("Error on delete: {e}"));
-                            }
-                        },
+        match msg {
+            SubsystemMessage::Loaded(generation, res) => {
+                //A newer load has been started since, so this result is stale.
+                if generation != self.generation {
+                    return Task::none();
+                }
 
-                        _ => (),
+                match res {
+                    Ok(data) => {
+                        let select = self.select_after_load.take();
+                        self.nav_bar = fill_nav(data, |s: &Subsystem| s.name.clone(), select.as_deref());
+                        self.state = LoadState::Loaded;
                     }
+                    Err(e) => self.state = LoadState::Failed(e),
                 }
-                SubsystemMessage::HandleSubButton(e) => {
-                    match self.sub_actions.data::<SubsystemMessage>(e).unwrap() {
-                        SubsystemMessage::Start => {
-                            self.dialog_string = Some("Starting subsystem".into());
-                            match data.start() {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    self.error_status = Some(format!("Error on start: {e}"));
-                                }
-                            }
-                        }
-                        SubsystemMessage::Stop => match data.stop() {
-                            Ok(_) => {}
-                            Err(e) => {
-                                self.error_status = Some(format!("Error on stop: {e}"));
-                            }
-                        },
-                        SubsystemMessage::Autoremove => match data.autoremove() {
-                            Ok(_) => {}
-                            Err(e) => {
-                                self.error_status = Some(format!("Error on autoremove: {e}"));
-                            }
-                        },
-                        SubsystemMessage::CleanPackageManagerCache => match data.clean() {
-                            Ok(_) => {}
-                            Err(e) => {
-                                self.error_status = Some(format!("Error on clean: {e}"));
-                            }
-                        },
-                        _ => (),
+            }
+            SubsystemMessage::Focus(name) => {
+                return match find_by_name(&self.nav_bar, |s: &Subsystem| &s.name, &name) {
+                    Some(entity) => self.on_select(entity),
+                    None => {
+                        self.select_after_load = Some(name);
+                        self.update_items()
                     }
+                };
+            }
+            SubsystemMessage::New => return self.open_wizard(None),
+            SubsystemMessage::NewWithStack(stack) => return self.open_wizard(stack),
+            SubsystemMessage::Wizard(WizardMessage::Cancel) => self.wizard = None,
+            SubsystemMessage::Wizard(WizardMessage::Finished(Ok(created))) => {
+                self.wizard = None;
+                let notice =
+                    notifications::send(Notice::for_subsystem("Subsystem creation", &created.name, &Ok(())));
+
+                match find_by_name(&self.nav_bar, |s: &Subsystem| &s.name, &created.name) {
+                    Some(m) => self.nav_bar.activate(m),
+                    None => self.select_after_load = Some(created.name),
                 }
-                SubsystemMessage::Reset => match data.reset(true) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        self.error_status = Some(format!("Error on reset: {e}"));
-                    }
-                },
-                SubsystemMessage::Start => match data.start() {
-                    Ok(_) => {}
-                    Err(e) => {
-                        self.error_status = Some(format!("Error on start: {e}"));
-                    }
-                },
-                SubsystemMessage::Stop => match data.stop() {
-                    Ok(_) => {}
-                    Err(e) => {
-                        self.error_status = Some(format!("Error on stop: {e}"));
-                    }
-                },
-                SubsystemMessage::Autoremove => match data.autoremove() {
-                    Ok(_) => {}
-                    Err(e) => {
-                        self.error_status = Some(format!("Error on autoremove: {e}"));
-                    }
-                },
-                SubsystemMessage::CleanPackageManagerCache => match data.clean() {
-                    Ok(_) => {}
-                    Err(e) => {
-                        self.error_status = Some(format!("Error on clean: {e}"));
+
+                return Task::batch([notice, self.update_items()]);
+            }
+            SubsystemMessage::Wizard(msg) => {
+                let wizard = match self.wizard.as_mut() {
+                    Some(wizard) => wizard,
+                    None => return Task::none(),
+                };
+
+                if let WizardMessage::Finished(Err(e)) = &msg {
+                    let res = Err(e.clone());
+                    let notice = notifications::send(Notice::for_subsystem(
+                        "Subsystem creation",
+                        wizard.name(),
+                        &res,
+                    ));
+
+                    return Task::batch([notice, wizard.update(msg)]);
+                }
+
+                return wizard.update(msg);
+            }
+            SubsystemMessage::HandleDestButton(e) => {
+                let action = self.destructive_actions.data::<SubsystemMessage>(e).cloned();
+                if let Some(action) = action {
+                    return self.handle_action(action);
+                }
+            }
+            SubsystemMessage::HandleSubButton(e) => {
+                let action = self.sub_actions.data::<SubsystemMessage>(e).cloned();
+                if let Some(action) = action {
+                    return self.handle_action(action);
+                }
+            }
+            SubsystemMessage::ActionFinished(label, name, res) => {
+                self.action_status = None;
+
+                //Resets and deletes take a while, so tell the user if they looked away.
+                let notice = match label {
+                    "reset" => notifications::send(Notice::for_subsystem("Reset", &name, &res)),
+                    //A deleted subsystem can't be shown anymore.
+                    "delete" => notifications::send(match res.is_ok() {
+                        true => Notice::for_subsystem("Delete", &name, &res).without_focus(),
+                        false => Notice::for_subsystem("Delete", &name, &res),
+                    }),
+                    _ => Task::none(),
+                };
+
+                if let Err(e) = res {
+                    self.error_status = Some(format!("Error on {label}: {e}"));
+                }
+
+                //Status (or the subsystem itself) will have changed.
+                return Task::batch([notice, self.update_items()]);
+            }
+            SubsystemMessage::TerminalOpened(res) => {
+                if let Err(e) = res {
+                    self.error_status = Some(format!("Error on open terminal: {e}"));
+                }
+            }
+            SubsystemMessage::Confirm(ConfirmMessage::Cancel) => self.confirm = None,
+            SubsystemMessage::Confirm(msg) => {
+                let confirmed = match self.confirm.as_mut() {
+                    Some((_, dialog)) => dialog.update(msg),
+                    None => false,
+                };
+
+                if confirmed {
+                    if let Some((action, dialog)) = self.confirm.take() {
+                        let force = dialog.force();
+
+                        return match action {
+                            SubsystemMessage::Reset => {
+                                self.run_action("reset", move |s| s.reset(force))
+                            }
+                            _ => self.run_action("delete", move |s| s.remove(force)),
+                        };
                     }
-                },
-                SubsystemMessage::Delete => match data.remove(true) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        self.error_status = Some(format!("Error on delete: {e}"));
+                }
+            }
+            SubsystemMessage::LoadApps => return self.load_apps(),
+            SubsystemMessage::AppsLoaded(name, res) => {
+                //Drop results for a subsystem that is no longer selected.
+                if self.nav_bar.active_data::<Subsystem>().map(|s| &s.name) != Some(&name) {
+                    return Task::none();
+                }
+
+                self.apps_loading = false;
+
+                match res {
+                    Ok((apps, binaries)) => {
+                        self.apps = Some(apps);
+                        self.binaries = binaries;
                     }
-                },
-                SubsystemMessage::CloseError => self.error_status = None,
-                SubsystemMessage::CloseAction => self.action_status = None,
-            },
-            _ => {}
+                    Err(e) => self.error_status = Some(format!("Error on listing applications: {e}")),
+                }
+            }
+            SubsystemMessage::ToggleExport(app, export) => {
+                return match export {
+                    true => self.run_app_action("export", move |s| s.export_app(&app.id)),
+                    false => self.run_app_action("unexport", move |s| s.unexport_app(&app.id)),
+                };
+            }
+            SubsystemMessage::Launch(app) => {
+                return self.run_app_action("launch", move |s| s.launch_app(&app));
+            }
+            SubsystemMessage::BinaryInputEdited(text) => self.binary_input = text,
+            SubsystemMessage::ExportBinary => {
+                let binary = self.binary_input.trim().to_string();
+                return self.run_app_action("export", move |s| s.export_binary(&binary));
+            }
+            SubsystemMessage::UnexportBinary(binary) => {
+                return self.run_app_action("unexport", move |s| s.unexport_binary(&binary));
+            }
+            SubsystemMessage::AppActionFinished(label, res) => {
+                self.apps_loading = false;
+
+                match res {
+                    Ok(_) if label == "launch" => {}
+                    Ok(_) => return self.load_apps(),
+                    Err(e) => self.error_status = Some(format!("Error on {label}: {e}")),
+                }
+            }
+            SubsystemMessage::CloseError => self.error_status = None,
+            action => return self.handle_action(action),
         }
+
+        Task::none()
     }
 }
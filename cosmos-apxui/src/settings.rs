@@ -0,0 +1,247 @@
+use crate::app::Message;
+use crate::config::Config;
+use crate::pages::blocking;
+use apx_shim::command::ExecutionStrategy;
+use apx_shim::terminal::{self, Terminal};
+use apx_shim::Stack;
+use cosmic::{
+    app::Task,
+    cosmic_theme::Spacing,
+    widget::{self, dropdown, text_input, toggler},
+    Element,
+};
+
+/// Choices offered for the auto-refresh interval, in seconds.
+const REFRESH_INTERVALS: [u32; 5] = [0, 10, 30, 60, 300];
+
+/// The settings page shown in the context drawer.
+pub struct SettingsPage {
+    stack_names: Vec<String>,
+    terminals: Vec<Terminal>,
+    terminal_names: Vec<String>,
+    refresh_labels: Vec<String>,
+    strategy_labels: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsMessage {
+    Loaded(Result<(Vec<String>, Vec<Terminal>), String>),
+    DefaultStack(usize),
+    Terminal(usize),
+    TerminalCommand(String),
+    AutoRefresh(usize),
+    ConfirmDestructive(bool),
+    ShowBuiltInStacks(bool),
+    ShowBuiltInPkgManagers(bool),
+    ExecutionStrategy(usize),
+}
+
+impl From<SettingsMessage> for Message {
+    fn from(message: SettingsMessage) -> Self {
+        Message::Settings(message)
+    }
+}
+
+fn strategy_label(strategy: ExecutionStrategy) -> &'static str {
+    match strategy {
+        ExecutionStrategy::Auto => "Automatic",
+        ExecutionStrategy::Direct => "Run apx directly",
+        ExecutionStrategy::HostSpawn => "Through host-spawn",
+        ExecutionStrategy::FlatpakSpawn => "Through flatpak-spawn",
+    }
+}
+
+fn refresh_label(secs: u32) -> String {
+    match secs {
+        0 => "Off".into(),
+        s if s % 60 == 0 => format!("Every {} min", s / 60),
+        s => format!("Every {s} s"),
+    }
+}
+
+impl SettingsPage {
+    pub fn new() -> Self {
+        Self {
+            stack_names: vec!["First available".into()],
+            terminals: vec![],
+            terminal_names: vec!["Automatic".into(), "Custom".into()],
+            refresh_labels: REFRESH_INTERVALS.iter().map(|s| refresh_label(*s)).collect(),
+            strategy_labels: ExecutionStrategy::ALL
+                .iter()
+                .map(|s| strategy_label(*s).to_string())
+                .collect(),
+        }
+    }
+
+    /// Loads the stacks and installed terminals offered as choices.
+    pub fn load(&self) -> Task<Message> {
+        Task::perform(
+            blocking(|| {
+                let stacks = Stack::get_all()?.into_iter().map(|s| s.name).collect();
+                Ok((stacks, terminal::detect()))
+            }),
+            |res| Message::from(SettingsMessage::Loaded(res)).into(),
+        )
+    }
+
+    /// Applies a settings change to `config`, returning false for messages that don't
+    /// change it.
+    pub fn update(&mut self, message: SettingsMessage, config: &mut Config) -> bool {
+        match message {
+            SettingsMessage::Loaded(res) => {
+                let (stacks, terminals) = match res {
+                    Ok(v) => v,
+                    Err(e) => {
+                        tracing::warn!("Could not load settings choices: {e}");
+                        return false;
+                    }
+                };
+
+                self.stack_names = std::iter::once("First available".to_string())
+                    .chain(stacks)
+                    .collect();
+                self.terminal_names = std::iter::once("Automatic".to_string())
+                    .chain(terminals.iter().map(|t| t.name.to_string()))
+                    .chain(std::iter::once("Custom".to_string()))
+                    .collect();
+                self.terminals = terminals;
+
+                return false;
+            }
+            SettingsMessage::DefaultStack(i) => {
+                config.default_stack = match i {
+                    0 => String::new(),
+                    i => self.stack_names.get(i).cloned().unwrap_or_default(),
+                }
+            }
+            SettingsMessage::Terminal(i) => {
+                config.terminal_command = match i {
+                    0 => String::new(),
+                    i => match self.terminals.get(i - 1) {
+                        Some(t) => t.template(),
+                        // "Custom" starts from a template to edit.
+                        None => format!("my-terminal -e {}", terminal::COMMAND_PLACEHOLDER),
+                    },
+                }
+            }
+            SettingsMessage::TerminalCommand(command) => config.terminal_command = command,
+            SettingsMessage::AutoRefresh(i) => {
+                config.auto_refresh_secs = REFRESH_INTERVALS.get(i).copied().unwrap_or(0)
+            }
+            SettingsMessage::ConfirmDestructive(v) => config.confirm_destructive = v,
+            SettingsMessage::ShowBuiltInStacks(v) => config.show_built_in_stacks = v,
+            SettingsMessage::ShowBuiltInPkgManagers(v) => config.show_built_in_pkg_managers = v,
+            SettingsMessage::ExecutionStrategy(i) => {
+                config.execution_strategy = ExecutionStrategy::ALL
+                    .get(i)
+                    .copied()
+                    .unwrap_or_default()
+            }
+        }
+
+        true
+    }
+
+    fn selected_terminal(&self, config: &Config) -> usize {
+        if config.terminal_command.is_empty() {
+            return 0;
+        }
+
+        match self
+            .terminals
+            .iter()
+            .position(|t| t.template() == config.terminal_command)
+        {
+            Some(i) => i + 1,
+            None => self.terminal_names.len() - 1,
+        }
+    }
+
+    pub fn view<'a>(&'a self, config: &'a Config) -> Element<'a, Message> {
+        let spacing = Spacing::default();
+
+        let default_stack = match config.default_stack.is_empty() {
+            true => Some(0),
+            false => self.stack_names.iter().position(|s| s == &config.default_stack),
+        };
+
+        let terminal = self.selected_terminal(config);
+        let custom = terminal == self.terminal_names.len() - 1;
+
+        let refresh = REFRESH_INTERVALS
+            .iter()
+            .position(|s| *s == config.auto_refresh_secs);
+
+        let strategy = ExecutionStrategy::ALL
+            .iter()
+            .position(|s| *s == config.execution_strategy);
+
+        let mut column = widget::column()
+            .spacing(spacing.space_m)
+            .push(widget::text::heading("Subsystems"))
+            .push(
+                widget::column()
+                    .spacing(spacing.space_xxs)
+                    .push(widget::text::caption("Default stack for new subsystems"))
+                    .push(dropdown(&self.stack_names, default_stack, |i| {
+                        SettingsMessage::DefaultStack(i).into()
+                    })),
+            )
+            .push(
+                widget::column()
+                    .spacing(spacing.space_xxs)
+                    .push(widget::text::caption("Refresh the subsystem list"))
+                    .push(dropdown(&self.refresh_labels, refresh, |i| {
+                        SettingsMessage::AutoRefresh(i).into()
+                    })),
+            )
+            .push(
+                widget::column()
+                    .spacing(spacing.space_xxs)
+                    .push(widget::text::caption("Terminal"))
+                    .push(dropdown(&self.terminal_names, Some(terminal), |i| {
+                        SettingsMessage::Terminal(i).into()
+                    })),
+            );
+
+        if custom {
+            column = column
+                .push(
+                    text_input("my-terminal -e {command}", &config.terminal_command)
+                        .label("Terminal command")
+                        .on_input(|t| SettingsMessage::TerminalCommand(t).into()),
+                )
+                .push(widget::text::caption(
+                    "{command} is replaced with apx <name> enter",
+                ));
+        }
+
+        column
+            .push(
+                toggler(config.confirm_destructive)
+                    .label("Confirm destructive actions")
+                    .on_toggle(|v| SettingsMessage::ConfirmDestructive(v).into()),
+            )
+            .push(widget::text::heading("Lists"))
+            .push(
+                toggler(config.show_built_in_stacks)
+                    .label("Show built-in stacks")
+                    .on_toggle(|v| SettingsMessage::ShowBuiltInStacks(v).into()),
+            )
+            .push(
+                toggler(config.show_built_in_pkg_managers)
+                    .label("Show built-in package managers")
+                    .on_toggle(|v| SettingsMessage::ShowBuiltInPkgManagers(v).into()),
+            )
+            .push(widget::text::heading("Advanced"))
+            .push(
+                widget::column()
+                    .spacing(spacing.space_xxs)
+                    .push(widget::text::caption("Run apx"))
+                    .push(dropdown(&self.strategy_labels, strategy, |i| {
+                        SettingsMessage::ExecutionStrategy(i).into()
+                    })),
+            )
+            .into()
+    }
+}
@@ -7,7 +7,10 @@ use zbus::{
     zvariant::OwnedObjectPath, Connection
 };
 
-use super::{get_usage_data, manager::UDisks2ManagerProxy, PartitionModel, COMMON_PARTITION_TYPES};
+use super::{
+    get_usage_data, manager::UDisks2ManagerProxy, EraseMode, FilesystemType, FormatPartitionInfo, PartitionModel,
+    PartitionTypeInfo,
+};
 
 #[derive(Debug, Clone)]
 pub struct DriveModel {
@@ -42,8 +45,12 @@ pub struct CreatePartitionInfo
     pub max_size: u64,
     pub offset: u64,
     pub erase: bool,
+    pub filesystem: FilesystemType,
     pub selected_type: String, 
+    /// Index into `partition_types()`.
     pub selected_partitition_type: usize,
+    /// The drive's partition table type, `dos` or `gpt`.
+    pub table_type: String,
     pub password_protected: bool,
     pub password: String,
     pub confirmed_password: String,
@@ -51,6 +58,62 @@ pub struct CreatePartitionInfo
 
 }
 
+/// GPT partition names are stored as 36 UTF-16 code units.
+const MAX_PARTITION_NAME_LENGTH: usize = 36;
+
+impl CreatePartitionInfo
+{
+    /// Why the partition can't be created as entered, if it can't.
+    pub fn validate(&self) -> Result<(), String>
+    {
+        if self.size == 0 {
+            return Err("The partition needs a size".into());
+        }
+
+        if self.size > self.max_size {
+            return Err(format!("The partition can be at most {} bytes", self.max_size));
+        }
+
+        if self.partition_type().is_none() {
+            return Err("Choose a partition type".into());
+        }
+
+        if self.table_type == "gpt" && self.name.encode_utf16().count() > MAX_PARTITION_NAME_LENGTH {
+            return Err(format!("Partition names can be at most {} characters", MAX_PARTITION_NAME_LENGTH));
+        }
+
+        self.format_info().validate()
+    }
+
+    /// The types the new partition can be created with on its table.
+    pub fn partition_types(&self) -> Vec<&'static PartitionTypeInfo>
+    {
+        PartitionTypeInfo::common_for_table(&self.table_type)
+    }
+
+    pub fn partition_type(&self) -> Option<&'static PartitionTypeInfo>
+    {
+        self.partition_types().get(self.selected_partitition_type).copied()
+    }
+
+    /// How the new partition's filesystem is created; its label is the partition name.
+    pub fn format_info(&self) -> FormatPartitionInfo
+    {
+        FormatPartitionInfo {
+            name: self.name.clone(),
+            filesystem: self.filesystem,
+            erase: match self.erase {
+                true => EraseMode::Zero,
+                false => EraseMode::None,
+            },
+            take_ownership: self.filesystem.is_posix(),
+            password_protected: self.password_protected,
+            password: self.password.clone(),
+            confirmed_password: self.confirmed_password.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct DriveBlockPair {
     block_path: OwnedObjectPath,
@@ -91,6 +154,24 @@ impl DriveModel {
         })
     }
 
+    /// Reads the drive's own properties back from UDisks2, keeping the partitions already loaded.
+    pub async fn reload(&self) -> Result<DriveModel>
+    {
+        let proxy = DriveProxy::builder(&self.connection).path(self.path.clone())?.build().await?;
+        let mut drive = DriveModel::from_proxy(&self.path, &self.block_path, &proxy).await?;
+
+        drive.partitions = self.partitions.clone();
+        drive.partition_table_type = self.partition_table_type.clone();
+
+        Ok(drive)
+    }
+
+    /// The partition at `path`, if it's on this drive.
+    pub fn partition(&self, path: &str) -> Option<&PartitionModel>
+    {
+        self.partitions.iter().find(|p| p.path.as_str() == path)
+    }
+
     pub async fn eject(&self) -> Result<()>
     {
         let proxy = DriveProxy::builder(&self.connection).path(self.path.clone())?.build().await?;
@@ -108,11 +189,24 @@ impl DriveModel {
 
     pub async fn create_partition(&self, info: CreatePartitionInfo) -> Result<()>
     {
+        info.validate().map_err(anyhow::Error::msg)?;
+
         let partition_table_proxy = PartitionTableProxy::builder(&self.connection).path(self.block_path.clone())?.build().await?;
 
-        let partition_type = &COMMON_PARTITION_TYPES[info.selected_partitition_type].ty;
+        let partition_type = info.partition_type().map(|t| t.ty).unwrap_or_default();
+
+        //DOS partitions don't have names; there the name only labels the filesystem.
+        let name = match info.table_type.as_str() {
+            "gpt" => info.name.as_str(),
+            _ => "",
+        };
+
+        let format = info.format_info();
+        let mut format_options = format.options();
+        //Keep the partition type that was picked, rather than one derived from the filesystem.
+        format_options.remove("update-partition-type");
 
-        partition_table_proxy.create_partition_and_format(info.offset, info.size, partition_type, &info.name, HashMap::new(), partition_type, HashMap::new()).await?;
+        partition_table_proxy.create_partition_and_format(info.offset, info.size, partition_type, name, HashMap::new(), format.filesystem.id(), format_options).await?;
 
         Ok(())
     }
@@ -0,0 +1,328 @@
+use std::{collections::HashMap, fmt::Display, sync::LazyLock};
+
+use zbus::zvariant::Value;
+
+/// Filesystems UDisks2 can create with `Block.Format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilesystemType {
+    #[default]
+    Ext4,
+    Xfs,
+    Btrfs,
+    Vfat,
+    Exfat,
+    Ntfs,
+    Swap,
+    /// Wipes existing signatures without creating a filesystem.
+    Empty,
+}
+
+impl FilesystemType {
+    pub const ALL: [FilesystemType; 8] = [
+        FilesystemType::Ext4,
+        FilesystemType::Xfs,
+        FilesystemType::Btrfs,
+        FilesystemType::Vfat,
+        FilesystemType::Exfat,
+        FilesystemType::Ntfs,
+        FilesystemType::Swap,
+        FilesystemType::Empty,
+    ];
+
+    /// The type string UDisks2 expects, which is also what it reports as `IdType`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            FilesystemType::Ext4 => "ext4",
+            FilesystemType::Xfs => "xfs",
+            FilesystemType::Btrfs => "btrfs",
+            FilesystemType::Vfat => "vfat",
+            FilesystemType::Exfat => "exfat",
+            FilesystemType::Ntfs => "ntfs",
+            FilesystemType::Swap => "swap",
+            FilesystemType::Empty => "empty",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.id() == id)
+    }
+
+    /// Whether the filesystem has Unix owners and permissions.
+    pub fn is_posix(&self) -> bool {
+        matches!(self, FilesystemType::Ext4 | FilesystemType::Xfs | FilesystemType::Btrfs)
+    }
+
+    /// The longest label the filesystem accepts, or 0 if it can't be labelled.
+    /// Counted in bytes for the Linux filesystems and swap, and in characters otherwise.
+    pub fn max_label_length(&self) -> usize {
+        match self {
+            FilesystemType::Ext4 => 16,
+            FilesystemType::Xfs => 12,
+            FilesystemType::Btrfs => 255,
+            FilesystemType::Vfat => 11,
+            FilesystemType::Exfat => 15,
+            FilesystemType::Ntfs => 128,
+            FilesystemType::Swap => 15,
+            FilesystemType::Empty => 0,
+        }
+    }
+
+    /// Checks `label` fits the filesystem, returning it the way it will be stored.
+    pub fn validate_label(&self, label: &str) -> Result<String, String> {
+        let max = self.max_label_length();
+
+        let length = match self {
+            FilesystemType::Ext4 | FilesystemType::Xfs | FilesystemType::Btrfs | FilesystemType::Swap => label.len(),
+            FilesystemType::Exfat | FilesystemType::Ntfs => label.encode_utf16().count(),
+            FilesystemType::Vfat => label.chars().count(),
+            FilesystemType::Empty if label.is_empty() => 0,
+            FilesystemType::Empty => return Err("An empty partition can't have a label".into()),
+        };
+
+        if length > max {
+            return Err(format!("{} labels can be at most {} characters", self.id(), max));
+        }
+
+        match self {
+            FilesystemType::Vfat => {
+                if let Some(c) = label.chars().find(|c| !c.is_ascii() || "\"*+,/:;<=>?[\\]|".contains(*c)) {
+                    return Err(format!("FAT labels can't contain '{}'", c));
+                }
+
+                Ok(label.to_ascii_uppercase())
+            }
+            FilesystemType::Xfs if label.contains(' ') => Err("XFS labels can't contain spaces".into()),
+            _ => Ok(label.to_owned()),
+        }
+    }
+
+    /// Whether the filesystem can be grown or shrunk, and if it has to be mounted for that.
+    pub fn resize_mode(&self, grow: bool) -> Option<ResizeMode> {
+        match (self, grow) {
+            (FilesystemType::Ext4, true) => Some(ResizeMode::Any),
+            (FilesystemType::Ext4, false) => Some(ResizeMode::Offline),
+            (FilesystemType::Xfs, true) => Some(ResizeMode::Online),
+            (FilesystemType::Btrfs, _) => Some(ResizeMode::Online),
+            (FilesystemType::Vfat | FilesystemType::Ntfs, _) => Some(ResizeMode::Offline),
+            //Nothing to resize but the partition.
+            (FilesystemType::Empty, _) => Some(ResizeMode::Any),
+            _ => None,
+        }
+    }
+
+    /// Space to leave on top of the data in use when shrinking a `size` byte filesystem.
+    /// Metadata, journals and reserved blocks aren't all counted as used, and the
+    /// resize tools refuse to shrink right down to the data.
+    pub fn shrink_margin(&self, size: u64) -> u64 {
+        const MIB: u64 = 1024 * 1024;
+
+        match self {
+            FilesystemType::Ext4 | FilesystemType::Ntfs => (size / 20).max(64 * MIB),
+            //Btrfs allocates metadata in chunks of its own, and won't go much below 256MiB.
+            FilesystemType::Btrfs => (size / 10).max(256 * MIB),
+            FilesystemType::Vfat => (size / 100).max(16 * MIB),
+            FilesystemType::Xfs | FilesystemType::Exfat | FilesystemType::Swap | FilesystemType::Empty => 0,
+        }
+    }
+
+    /// Whether UDisks2 can check and repair the filesystem.
+    pub fn can_check(&self) -> bool {
+        !matches!(self, FilesystemType::Swap | FilesystemType::Empty)
+    }
+
+    /// Whether UDisks2 can change the filesystem's label. Swap space has no
+    /// `Filesystem` interface to set it through.
+    pub fn supports_label_change(&self) -> bool {
+        !matches!(self, FilesystemType::Swap | FilesystemType::Empty)
+    }
+
+    /// Whether UDisks2 can change the filesystem's UUID.
+    pub fn supports_uuid_change(&self) -> bool {
+        self.supports_label_change()
+    }
+
+    /// Checks `uuid` is in the form the filesystem uses: a volume serial like `1234-ABCD` for
+    /// FAT and exFAT, 16 hex digits for NTFS, and a standard UUID otherwise.
+    pub fn validate_uuid(&self, uuid: &str) -> Result<(), String> {
+        let is_hex = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit());
+
+        let valid = match self {
+            FilesystemType::Vfat | FilesystemType::Exfat => {
+                uuid.len() == 9 && uuid.split('-').all(|part| part.len() == 4 && is_hex(part))
+            }
+            FilesystemType::Ntfs => uuid.len() == 16 && is_hex(uuid),
+            FilesystemType::Empty => false,
+            _ => {
+                let parts: Vec<&str> = uuid.split('-').collect();
+                parts.iter().map(|p| p.len()).eq([8, 4, 4, 4, 12]) && parts.iter().all(|p| is_hex(p))
+            }
+        };
+
+        match valid {
+            true => Ok(()),
+            false => Err(format!("'{}' isn't a valid {} UUID", uuid, self.id())),
+        }
+    }
+
+    /// A new random UUID in the form the filesystem uses.
+    pub fn generate_uuid(&self) -> std::io::Result<String> {
+        let uuid = std::fs::read_to_string("/proc/sys/kernel/random/uuid")?.trim().to_owned();
+        let hex: String = uuid.chars().filter(|c| *c != '-').collect::<String>().to_uppercase();
+
+        Ok(match self {
+            FilesystemType::Vfat | FilesystemType::Exfat => format!("{}-{}", &hex[0..4], &hex[4..8]),
+            FilesystemType::Ntfs => hex[0..16].to_owned(),
+            _ => uuid,
+        })
+    }
+}
+
+impl Display for FilesystemType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FilesystemType::Ext4 => "Ext4 - Linux",
+            FilesystemType::Xfs => "XFS - Linux",
+            FilesystemType::Btrfs => "Btrfs - Linux",
+            FilesystemType::Vfat => "FAT - All systems",
+            FilesystemType::Exfat => "exFAT - All systems",
+            FilesystemType::Ntfs => "NTFS - Windows",
+            FilesystemType::Swap => "Swap - Linux",
+            FilesystemType::Empty => "Empty - No filesystem",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+pub static FILESYSTEM_NAMES: LazyLock<Vec<String>> =
+    LazyLock::new(|| FilesystemType::ALL.iter().map(|f| f.to_string()).collect());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeMode {
+    /// Only while mounted.
+    Online,
+    /// Only while unmounted.
+    Offline,
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EraseMode {
+    /// Only the existing signatures are wiped.
+    #[default]
+    None,
+    /// Every byte is overwritten with zeroes, which can take a long time.
+    Zero,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FormatPartitionInfo {
+    pub name: String,
+    pub filesystem: FilesystemType,
+    pub erase: EraseMode,
+    pub take_ownership: bool,
+    pub password_protected: bool,
+    pub password: String,
+    pub confirmed_password: String,
+}
+
+impl FormatPartitionInfo {
+    /// Why the options can't be submitted yet, if they can't.
+    pub fn validate(&self) -> Result<(), String> {
+        self.filesystem.validate_label(&self.name)?;
+
+        if self.password_protected {
+            if self.password.is_empty() {
+                return Err("Enter a password".into());
+            }
+
+            if self.password != self.confirmed_password {
+                return Err("The passwords don't match".into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Options for `Block.Format`, also used for the filesystem in `PartitionTable.CreatePartitionAndFormat`.
+    /// Only meaningful once `validate` passed.
+    pub(crate) fn options(&self) -> HashMap<&str, Value<'_>> {
+        let mut options = HashMap::new();
+
+        if let Ok(label) = self.filesystem.validate_label(&self.name) {
+            if !label.is_empty() {
+                options.insert("label", Value::from(label));
+            }
+        }
+
+        if self.take_ownership && self.filesystem.is_posix() {
+            options.insert("take-ownership", Value::from(true));
+        }
+
+        if self.erase == EraseMode::Zero {
+            options.insert("erase", Value::from("zero"));
+        }
+
+        if self.password_protected {
+            options.insert("encrypt.passphrase", Value::from(self.password.as_str()));
+            options.insert("encrypt.type", Value::from("luks2"));
+        }
+
+        options.insert("update-partition-type", Value::from(true));
+
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fat_labels_are_uppercased_and_restricted() {
+        assert_eq!(FilesystemType::Vfat.validate_label("data 1"), Ok("DATA 1".into()));
+        assert!(FilesystemType::Vfat.validate_label("ABCDEFGHIJK").is_ok());
+        assert!(FilesystemType::Vfat.validate_label("ABCDEFGHIJKL").is_err());
+
+        for label in ["a+b", "a,b", "a;b", "a=b", "a[b", "a]b", "a*b", "a/b", "a\\b", "daté"] {
+            assert!(FilesystemType::Vfat.validate_label(label).is_err(), "{label} should be rejected");
+        }
+    }
+
+    #[test]
+    fn label_limits_count_bytes_or_utf16_units() {
+        //16 bytes fit ext4, 9 two-byte characters don't.
+        assert!(FilesystemType::Ext4.validate_label("abcdefghijklmnop").is_ok());
+        assert!(FilesystemType::Ext4.validate_label("ééééééééé").is_err());
+
+        //exFAT counts UTF-16 units, where é is one and 😀 is two.
+        assert!(FilesystemType::Exfat.validate_label(&"é".repeat(15)).is_ok());
+        assert!(FilesystemType::Exfat.validate_label(&"é".repeat(16)).is_err());
+        assert!(FilesystemType::Exfat.validate_label(&"😀".repeat(8)).is_err());
+        assert!(FilesystemType::Ntfs.validate_label(&"😀".repeat(64)).is_ok());
+
+        assert!(FilesystemType::Xfs.validate_label("my disk").is_err());
+        assert!(FilesystemType::Empty.validate_label("").is_ok());
+        assert!(FilesystemType::Empty.validate_label("disk").is_err());
+    }
+
+    #[test]
+    fn uuids_take_the_filesystems_shape() {
+        assert!(FilesystemType::Vfat.validate_uuid("1234-ABCD").is_ok());
+        assert!(FilesystemType::Exfat.validate_uuid("1234ABCD").is_err());
+        assert!(FilesystemType::Ntfs.validate_uuid("0123456789ABCDEF").is_ok());
+        assert!(FilesystemType::Ntfs.validate_uuid("0123456789ABCDE").is_err());
+        assert!(FilesystemType::Ext4.validate_uuid("0fc63daf-8483-4772-8e79-3d69d8477de4").is_ok());
+        assert!(FilesystemType::Btrfs.validate_uuid("0fc63daf84834772").is_err());
+        assert!(FilesystemType::Xfs.validate_uuid("0fc63daf-8483-4772-8e79-3d69d8477dz4").is_err());
+        assert!(FilesystemType::Empty.validate_uuid("1234-ABCD").is_err());
+    }
+
+    #[test]
+    fn generated_uuids_validate() {
+        for filesystem in [FilesystemType::Vfat, FilesystemType::Exfat, FilesystemType::Ntfs, FilesystemType::Ext4] {
+            let uuid = filesystem.generate_uuid().unwrap();
+            assert_eq!(filesystem.validate_uuid(&uuid), Ok(()), "{uuid}");
+        }
+    }
+}
@@ -1,14 +1,16 @@
 use std::collections::HashMap;
 use std::time::Duration;
 use anyhow::Result;
-use futures::stream::Stream;
+use futures::stream::{Stream, StreamExt};
 use futures::task::{Context, Poll};
 use tokio::sync::mpsc;
 use tokio::time::sleep;
-use tracing::error;
+use tracing::{error, warn};
 use zbus::{
+    fdo::{ObjectManagerProxy, PropertiesChanged},
+    message::Type as MessageType,
     zvariant::{self, Value},
-    Connection,
+    Connection, MatchRule, MessageStream,
 };
 use zbus_macros::proxy;
 
@@ -26,14 +28,105 @@ pub trait UDisks2Manager {
     ) -> zbus::Result<Vec<zvariant::OwnedObjectPath>>;
 }
 
+const DRIVE_INTERFACE: &str = "org.freedesktop.UDisks2.Drive";
+const BLOCK_INTERFACE: &str = "org.freedesktop.UDisks2.Block";
+const PARTITION_INTERFACE: &str = "org.freedesktop.UDisks2.Partition";
+const JOB_INTERFACE: &str = "org.freedesktop.UDisks2.Job";
+const FILESYSTEM_INTERFACE: &str = "org.freedesktop.UDisks2.Filesystem";
+
 pub struct DiskManager {
+    connection: Connection,
     proxy: UDisks2ManagerProxy<'static>,
 }
 
+/// What kind of UDisks2 object a device event is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Drive,
+    /// A block device that isn't a partition, e.g. a whole disk or a loop device.
+    Block,
+    Partition,
+    /// A long-running operation, such as a format or a filesystem check.
+    Job,
+}
+
+impl DeviceKind {
+    /// The kind of object that has `interfaces`, if any of them is one we track.
+    /// Objects only count as added or removed when this interface comes or goes, so a
+    /// filesystem appearing on an existing partition isn't mistaken for a new device.
+    fn from_interfaces<'a>(interfaces: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        let interfaces: Vec<&str> = interfaces.into_iter().collect();
+
+        if interfaces.contains(&DRIVE_INTERFACE) {
+            Some(DeviceKind::Drive)
+        } else if interfaces.contains(&JOB_INTERFACE) {
+            Some(DeviceKind::Job)
+        } else if interfaces.contains(&PARTITION_INTERFACE) {
+            Some(DeviceKind::Partition)
+        } else if interfaces.contains(&BLOCK_INTERFACE) {
+            Some(DeviceKind::Block)
+        } else {
+            None
+        }
+    }
+}
+
+/// A change to an object that's already known, from its `PropertiesChanged` signal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceChange {
+    Drive(String),
+    Block(String),
+    Partition(String),
+    /// A filesystem was mounted or unmounted, with where it's mounted now.
+    MountPoints(String, Vec<String>),
+}
+
+impl DeviceChange {
+    /// The object path of whatever changed.
+    pub fn path(&self) -> &str {
+        match self {
+            DeviceChange::Drive(path)
+            | DeviceChange::Block(path)
+            | DeviceChange::Partition(path)
+            | DeviceChange::MountPoints(path, _) => path,
+        }
+    }
+
+    fn from_signal(path: String, interface: &str, changed: &HashMap<&str, Value<'_>>) -> Option<Self> {
+        match interface {
+            DRIVE_INTERFACE => Some(DeviceChange::Drive(path)),
+            BLOCK_INTERFACE => Some(DeviceChange::Block(path)),
+            PARTITION_INTERFACE => Some(DeviceChange::Partition(path)),
+            FILESYSTEM_INTERFACE => {
+                let mount_points = changed.get("MountPoints")?;
+                Some(DeviceChange::MountPoints(path, decode_mount_points(mount_points)))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Mount points are sent as NUL-terminated byte arrays.
+fn decode_mount_points(value: &Value<'_>) -> Vec<String> {
+    let arrays: Vec<Vec<u8>> = match value.try_clone().ok().and_then(|v| v.try_into().ok()) {
+        Some(arrays) => arrays,
+        None => return vec![],
+    };
+
+    arrays
+        .into_iter()
+        .map(|bytes| {
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).into_owned()
+        })
+        .collect()
+}
+
 #[derive(Debug, PartialEq)]
 pub enum DeviceEvent {
-    Added(String),
-    Removed(String),
+    Added(DeviceKind, String),
+    Removed(DeviceKind, String),
+    Changed(DeviceChange),
 }
 
 pub struct DeviceEventStream {
@@ -45,56 +138,132 @@ impl DiskManager {
         let connection = Connection::system().await?;
         let proxy = UDisks2ManagerProxy::new(&connection).await?;
         Ok(Self {
+            connection,
             proxy,
         })
     }
 
+    /// Streams devices as they come and go, following UDisks2's ObjectManager signals.
+    /// If those can't be subscribed to, block devices are polled every `interval` instead.
     pub fn device_event_stream(&self, interval: Duration) -> DeviceEventStream {
         let (sender, receiver) = mpsc::channel(32); // Channel capacity of 32
+        let connection = self.connection.clone();
         let proxy = self.proxy.clone();
 
         tokio::spawn(async move {
-            let mut previous_devices: Option<Vec<String>> = None;
-            loop {
-                let current_devices = match proxy
-                    .get_block_devices(HashMap::new())
-                    .await
-                {
-                    Ok(paths) => paths.into_iter().map(|p| p.to_string()).collect(),
-                    Err(e) => {
-                        error!("Failed to get block devices: {}", e);
-                        Vec::new()
-                    }
-                };
+            match Self::watch_signals(&connection, &sender).await {
+                Ok(()) => return, // The receiver is gone.
+                Err(e) => warn!("Polling for device changes, as signals are unavailable: {}", e),
+            }
+
+            Self::poll_block_devices(proxy, sender, interval).await;
+        });
+
+        DeviceEventStream { receiver }
+    }
+
+    async fn watch_signals(connection: &Connection, sender: &mpsc::Sender<DeviceEvent>) -> Result<()> {
+        let object_manager = ObjectManagerProxy::builder(connection)
+            .destination("org.freedesktop.UDisks2")?
+            .path("/org/freedesktop/UDisks2")?
+            .build()
+            .await?;
+
+        let mut added = object_manager.receive_interfaces_added().await?;
+        let mut removed = object_manager.receive_interfaces_removed().await?;
+
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender("org.freedesktop.UDisks2")?
+            .interface("org.freedesktop.DBus.Properties")?
+            .member("PropertiesChanged")?
+            .path_namespace("/org/freedesktop/UDisks2")?
+            .build();
+        let mut properties = MessageStream::for_match_rule(rule, connection, Some(64)).await?;
 
-                let mut events = Vec::new();
-                if let Some(prev_devices) = &previous_devices {
-                    for device in &current_devices {
-                        if !prev_devices.contains(device) {
-                            events.push(DeviceEvent::Added(device.clone()));
+        loop {
+            let event = tokio::select! {
+                signal = added.next() => {
+                    let signal = signal.ok_or_else(|| anyhow::anyhow!("InterfacesAdded stream ended"))?;
+                    let args = signal.args()?;
+
+                    DeviceKind::from_interfaces(args.interfaces_and_properties().keys().map(|i| i.as_str()))
+                        .map(|kind| DeviceEvent::Added(kind, args.object_path().to_string()))
+                }
+                signal = removed.next() => {
+                    let signal = signal.ok_or_else(|| anyhow::anyhow!("InterfacesRemoved stream ended"))?;
+                    let args = signal.args()?;
+
+                    DeviceKind::from_interfaces(args.interfaces().iter().map(|i| i.as_str()))
+                        .map(|kind| DeviceEvent::Removed(kind, args.object_path().to_string()))
+                }
+                message = properties.next() => {
+                    let message = message.ok_or_else(|| anyhow::anyhow!("PropertiesChanged stream ended"))??;
+                    let path = message.header().path().map(|p| p.to_string());
+
+                    match (path, PropertiesChanged::from_message(message)) {
+                        (Some(path), Some(signal)) => {
+                            let args = signal.args()?;
+                            DeviceChange::from_signal(path, args.interface_name().as_str(), args.changed_properties())
+                                .map(DeviceEvent::Changed)
                         }
+                        _ => None,
                     }
+                }
+            };
 
-                    for device in prev_devices {
-                        if !current_devices.contains(device) {
-                            events.push(DeviceEvent::Removed(device.clone()));
-                        }
+            if let Some(event) = event {
+                if sender.send(event).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    // Diffs block device paths on an interval. Only used when signals aren't available.
+    async fn poll_block_devices(
+        proxy: UDisks2ManagerProxy<'static>,
+        sender: mpsc::Sender<DeviceEvent>,
+        interval: Duration,
+    ) {
+        let mut previous_devices: Option<Vec<String>> = None;
+        loop {
+            let current_devices = match proxy
+                .get_block_devices(HashMap::new())
+                .await
+            {
+                Ok(paths) => paths.into_iter().map(|p| p.to_string()).collect(),
+                Err(e) => {
+                    error!("Failed to get block devices: {}", e);
+                    Vec::new()
+                }
+            };
+
+            let mut events = Vec::new();
+            if let Some(prev_devices) = &previous_devices {
+                for device in &current_devices {
+                    if !prev_devices.contains(device) {
+                        events.push(DeviceEvent::Added(DeviceKind::Block, device.clone()));
                     }
                 }
 
-                for event in events {
-                    if let Err(e) = sender.send(event).await {
-                        error!("Failed to send event: {}", e);
-                        break; // Exit loop if sender is closed
+                for device in prev_devices {
+                    if !current_devices.contains(device) {
+                        events.push(DeviceEvent::Removed(DeviceKind::Block, device.clone()));
                     }
                 }
+            }
 
-                previous_devices = Some(current_devices);
-                sleep(interval).await;
+            for event in events {
+                if let Err(e) = sender.send(event).await {
+                    error!("Failed to send event: {}", e);
+                    return; // The receiver is gone.
+                }
             }
-        });
 
-        DeviceEventStream { receiver }
+            previous_devices = Some(current_devices);
+            sleep(interval).await;
+        }
     }
 
 
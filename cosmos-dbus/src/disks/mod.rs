@@ -3,12 +3,16 @@ mod partition;
 mod usage;
 mod manager;
 mod partition_type;
+mod format;
+mod mount_options;
 
 pub use drive::*;
-pub use partition::PartitionModel;
+pub use partition::{CheckOutcome, EditFilesystemInfo, EditPartitionInfo, PartitionModel, ResizePartitionInfo};
 pub use usage::*;
 pub use manager::*;
 pub use partition_type::*;
+pub use format::*;
+pub use mount_options::MountOptionsInfo;
 use thiserror::Error;
 
 // async fn get_size(path: impl Into<String> + std::fmt::Display) -> Result<String> {
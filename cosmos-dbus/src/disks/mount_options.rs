@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use zbus::zvariant::{OwnedValue, Value};
+use zbus_macros::proxy;
+
+/// An entry of `Block.Configuration`, e.g. `("fstab", {"dir": ..., "opts": ...})`.
+pub(crate) type ConfigurationItem = (String, HashMap<String, OwnedValue>);
+
+//The udisks2 crate's block proxy doesn't decode configuration items.
+#[proxy(
+    default_service = "org.freedesktop.UDisks2",
+    interface = "org.freedesktop.UDisks2.Block"
+)]
+pub(crate) trait BlockConfiguration {
+    #[zbus(property)]
+    fn configuration(&self) -> zbus::Result<Vec<ConfigurationItem>>;
+
+    #[zbus(property)]
+    fn symlinks(&self) -> zbus::Result<Vec<Vec<u8>>>;
+
+    fn add_configuration_item(
+        &self,
+        item: &ConfigurationItem,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    fn update_configuration_item(
+        &self,
+        old_item: &ConfigurationItem,
+        new_item: &ConfigurationItem,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    fn remove_configuration_item(
+        &self,
+        item: &ConfigurationItem,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+}
+
+/// How a partition is mounted, as an fstab entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MountOptionsInfo {
+    /// Leave mounting to the user session, without an fstab entry.
+    pub use_defaults: bool,
+    pub mount_at_startup: bool,
+    pub show_in_ui: bool,
+    pub require_auth: bool,
+    pub display_name: String,
+    pub icon_name: String,
+    pub symbolic_icon_name: String,
+    /// Every other mount option, comma separated.
+    pub options: String,
+    pub mount_point: String,
+    /// The fstab device field, e.g. `UUID=...` or a `/dev/disk/by-id/...` symlink.
+    pub identify_as: String,
+    /// What `identify_as` can be set to.
+    pub identify_choices: Vec<String>,
+    pub filesystem_type: String,
+}
+
+impl MountOptionsInfo {
+    /// Reads the options of an fstab entry into the fields they're edited with.
+    pub(crate) fn set_options(&mut self, options: &str) {
+        self.mount_at_startup = true;
+        self.show_in_ui = false;
+        self.require_auth = false;
+        self.display_name.clear();
+        self.icon_name.clear();
+        self.symbolic_icon_name.clear();
+
+        let mut other = vec![];
+        for option in options.split(',').filter(|o| !o.is_empty()) {
+            match option.split_once('=') {
+                Some(("x-gvfs-name", v)) => self.display_name = unescape(v),
+                Some(("x-gvfs-icon", v)) => self.icon_name = unescape(v),
+                Some(("x-gvfs-symbolic-icon", v)) => self.symbolic_icon_name = unescape(v),
+                _ => match option {
+                    "noauto" => self.mount_at_startup = false,
+                    "x-gvfs-show" => self.show_in_ui = true,
+                    "x-udisks-auth" => self.require_auth = true,
+                    "defaults" => {}
+                    _ => other.push(option),
+                },
+            }
+        }
+
+        self.options = other.join(",");
+    }
+
+    /// The options field of the fstab entry.
+    pub fn options_string(&self) -> String {
+        let mut options: Vec<String> = self
+            .options
+            .split(',')
+            .map(|o| o.trim())
+            .filter(|o| !o.is_empty())
+            .map(|o| o.to_owned())
+            .collect();
+
+        if !self.mount_at_startup {
+            options.push("noauto".into());
+        }
+
+        if self.show_in_ui {
+            options.push("x-gvfs-show".into());
+        }
+
+        if self.require_auth {
+            options.push("x-udisks-auth".into());
+        }
+
+        for (key, value) in [
+            ("x-gvfs-name", &self.display_name),
+            ("x-gvfs-icon", &self.icon_name),
+            ("x-gvfs-symbolic-icon", &self.symbolic_icon_name),
+        ] {
+            if !value.is_empty() {
+                options.push(format!("{}={}", key, escape(value)));
+            }
+        }
+
+        match options.is_empty() {
+            true => "defaults".into(),
+            false => options.join(","),
+        }
+    }
+
+    /// The line that will be written to `/etc/fstab`.
+    pub fn fstab_line(&self) -> String {
+        format!(
+            "{} {} {} {} 0 0",
+            escape_field(&self.identify_as),
+            escape_field(&self.mount_point),
+            self.filesystem_type,
+            self.options_string()
+        )
+    }
+
+    /// Why the entry can't be saved, if it can't.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.use_defaults {
+            return Ok(());
+        }
+
+        if !self.mount_point.starts_with('/') {
+            return Err("The mount point must be an absolute path".into());
+        }
+
+        if self.identify_as.is_empty() {
+            return Err("Choose how to identify the device".into());
+        }
+
+        if self.filesystem_type.is_empty() || self.filesystem_type.contains(char::is_whitespace) {
+            return Err("Enter a filesystem type, or auto".into());
+        }
+
+        if self.options.contains(char::is_whitespace) {
+            return Err("Mount options can't contain spaces".into());
+        }
+
+        Ok(())
+    }
+
+    /// The fstab configuration item for these options, keeping the dump and pass
+    /// numbers of `existing`.
+    pub(crate) fn to_item(&self, existing: Option<&ConfigurationItem>) -> Result<ConfigurationItem> {
+        let mut details = HashMap::new();
+
+        for (key, value) in [
+            ("fsname", self.identify_as.clone()),
+            ("dir", self.mount_point.clone()),
+            ("type", self.filesystem_type.clone()),
+            ("opts", self.options_string()),
+        ] {
+            details.insert(key.to_owned(), OwnedValue::try_from(Value::from(to_bytestring(&value)))?);
+        }
+
+        for key in ["freq", "passno"] {
+            let value = match existing.and_then(|(_, d)| d.get(key)) {
+                Some(v) => v.try_clone()?,
+                None => OwnedValue::from(0i32),
+            };
+            details.insert(key.to_owned(), value);
+        }
+
+        Ok(("fstab".to_owned(), details))
+    }
+}
+
+/// A string field of a configuration item.
+pub(crate) fn item_string(item: &ConfigurationItem, key: &str) -> String {
+    item.1
+        .get(key)
+        .and_then(|v| Vec::<u8>::try_from(v.try_clone().ok()?).ok())
+        .map(|bytes| from_bytestring(&bytes))
+        .unwrap_or_default()
+}
+
+/// UDisks2 sends paths as NUL-terminated byte arrays.
+pub(crate) fn from_bytestring(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn to_bytestring(value: &str) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+}
+
+//fstab fields are separated by whitespace, which is written as octal escapes like UDisks2 does.
+fn escape_field(value: &str) -> String {
+    value
+        .replace('\\', "\\134")
+        .replace(' ', "\\040")
+        .replace('\t', "\\011")
+        .replace('\n', "\\012")
+}
+
+//Option values can't contain the separators of fstab fields or options.
+fn escape(value: &str) -> String {
+    value.replace('%', "%25").replace(' ', "%20").replace(',', "%2C")
+}
+
+fn unescape(value: &str) -> String {
+    value.replace("%20", " ").replace("%2C", ",").replace("%25", "%")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_round_trip() {
+        let mut info = MountOptionsInfo::default();
+        info.set_options("noauto,x-gvfs-show,x-gvfs-name=My%20Disk");
+
+        assert!(!info.mount_at_startup);
+        assert!(info.show_in_ui);
+        assert!(!info.require_auth);
+        assert_eq!(info.display_name, "My Disk");
+        assert_eq!(info.options, "");
+        assert_eq!(info.options_string(), "noauto,x-gvfs-show,x-gvfs-name=My%20Disk");
+
+        info.set_options("nosuid,nodev,x-udisks-auth,x-gvfs-icon=drive%2Charddisk");
+
+        assert!(info.mount_at_startup);
+        assert!(info.require_auth);
+        assert_eq!(info.options, "nosuid,nodev");
+        assert_eq!(info.icon_name, "drive,harddisk");
+        assert_eq!(info.options_string(), "nosuid,nodev,x-udisks-auth,x-gvfs-icon=drive%2Charddisk");
+    }
+
+    #[test]
+    fn option_values_escape_and_unescape() {
+        for value in ["My Disk", "a,b", "100%", "50%20off", ""] {
+            assert_eq!(unescape(&escape(value)), value);
+        }
+        assert_eq!(escape("50% off, then"), "50%25%20off%2C%20then");
+    }
+
+    #[test]
+    fn no_options_are_defaults() {
+        let mut info = MountOptionsInfo::default();
+        info.set_options("defaults");
+
+        assert!(info.mount_at_startup);
+        assert_eq!(info.options_string(), "defaults");
+    }
+
+    #[test]
+    fn fstab_line_escapes_whitespace_in_fields() {
+        let mut info = MountOptionsInfo {
+            identify_as: "LABEL=My Disk".into(),
+            mount_point: "/mnt/my disk".into(),
+            filesystem_type: "auto".into(),
+            ..Default::default()
+        };
+        info.set_options("nofail");
+
+        assert_eq!(info.fstab_line(), "LABEL=My\\040Disk /mnt/my\\040disk auto nofail 0 0");
+    }
+}
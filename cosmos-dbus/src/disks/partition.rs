@@ -3,12 +3,81 @@ use enumflags2::{bitflags, BitFlags};
 use anyhow::Result;
 use udisks2::{block::BlockProxy, filesystem::FilesystemProxy, partition::{PartitionFlags, PartitionProxy}, Client};
 use zbus::{
-    zvariant::OwnedObjectPath, Connection}
+    zvariant::{OwnedObjectPath, Value}, Connection}
 ;
+use zbus_macros::proxy;
 
-use super::{ DiskError, Usage};
+use super::mount_options::{from_bytestring, item_string, BlockConfigurationProxy, ConfigurationItem};
+use super::{ get_usage_data, DiskError, FilesystemType, FormatPartitionInfo, MountOptionsInfo, PartitionTypeInfo, ResizeMode, Usage};
 
 
+//SetUUID isn't on the udisks2 crate's filesystem proxy.
+#[proxy(
+    default_service = "org.freedesktop.UDisks2",
+    interface = "org.freedesktop.UDisks2.Filesystem"
+)]
+trait FilesystemUuid {
+    #[zbus(name = "SetUUID")]
+    fn set_uuid(&self, uuid: &str, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EditFilesystemInfo
+{
+    pub filesystem: FilesystemType,
+    pub label: String,
+    pub uuid: String,
+    /// Replace the UUID with a random one, rather than `uuid`.
+    pub regenerate_uuid: bool,
+}
+
+impl EditFilesystemInfo
+{
+    /// Why the changes can't be applied, if they can't.
+    pub fn validate(&self) -> Result<(), String>
+    {
+        self.filesystem.validate_label(&self.label)?;
+
+        if !self.regenerate_uuid && self.filesystem.supports_uuid_change() {
+            self.filesystem.validate_uuid(&self.uuid)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How a filesystem check or repair turned out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckOutcome
+{
+    Clean,
+    ErrorsFound,
+    Repaired,
+    /// The filesystem can't be checked or repaired.
+    Unsupported,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResizePartitionInfo
+{
+    pub size: u64,
+    pub min_size: u64,
+    pub max_size: u64,
+}
+
+const MIB: u64 = 1024 * 1024;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EditPartitionInfo
+{
+    pub table_type: String,
+    pub type_id: String,
+    pub name: String,
+    pub flags: u64,
+    /// What the type list is filtered by.
+    pub filter: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct PartitionModel {
     pub is_contained: bool,
@@ -16,7 +85,13 @@ pub struct PartitionModel {
     pub table_path: OwnedObjectPath,
     pub name: String,
     pub partition_type: String,
+    /// The raw partition type, a GUID on GPT tables or a hex code like `0x83` on DOS tables.
+    pub type_id: String,
     pub id_type: String,
+    /// The filesystem's label, as opposed to the GPT partition name.
+    pub id_label: String,
+    /// The filesystem's UUID, as opposed to the GPT partition UUID.
+    pub id_uuid: String,
     pub uuid: String,
     pub number: u32,
     pub flags: BitFlags<PartitionFlags>,
@@ -25,6 +100,8 @@ pub struct PartitionModel {
     pub path: OwnedObjectPath,
     pub device_path: Option<String>,
     pub usage: Option<Usage>,
+    /// Where the filesystem is mounted, empty if it isn't.
+    pub mount_points: Vec<String>,
     connection: Option<Connection>,
     pub drive_path: String,
 
@@ -74,13 +151,22 @@ impl PartitionModel {
 
 
 
+        //Blocks without a filesystem don't have the interface to ask.
+        let mount_points = match FilesystemProxy::builder(block_proxy.inner().connection()).path(&partition_path)?.build().await {
+            Ok(proxy) => proxy.mount_points().await.unwrap_or_default().iter().map(|p| from_bytestring(p)).collect(),
+            Err(_) => vec![],
+        };
+
      Ok(Self {
             is_contained: partition_proxy.is_contained().await?,
             is_container: partition_proxy.is_container().await?,
             table_path: partition_proxy.table().await?,
             name: partition_proxy.name().await?,
             partition_type: type_str,
+            type_id: partition_proxy.type_().await?,
             id_type: block_proxy.id_type().await?,
+            id_label: block_proxy.id_label().await?,
+            id_uuid: block_proxy.id_uuid().await?,
             uuid: partition_proxy.uuid().await?,
             number: partition_proxy.number().await?,
             flags: partition_proxy.flags().await?,
@@ -89,12 +175,19 @@ impl PartitionModel {
             path: partition_path.clone(),
             device_path: device_path,
             usage,
+            mount_points,
             connection: Some(Connection::system().await?),
             drive_path: drive_path,
         })
     }
 
 
+    pub fn is_mounted(&self) -> bool
+    {
+        !self.mount_points.is_empty()
+    }
+
+
     pub async fn connect(&mut self) -> Result<()>
     {
         if self.connection.is_none()
@@ -157,33 +250,119 @@ impl PartitionModel {
     }
 
 
-    pub async fn format(&self, name: String, erase: bool, partion_type: String) -> Result<()>
+    /// Reads this partition back from UDisks2, e.g. after it was mounted or relabelled.
+    pub async fn reload(&self) -> Result<PartitionModel>
+    {
+        let connection = match &self.connection {
+            Some(c) => c.clone(),
+            None => Connection::system().await?,
+        };
+        let client = Client::new_for_connection(connection.clone()).await?;
+
+        let partition_proxy = PartitionProxy::builder(&connection).path(&self.path)?.build().await?;
+        let block_proxy = BlockProxy::builder(&connection).path(&self.path)?.build().await?;
+
+        let short_name = self.path.as_str().split("/").last();
+        let usage = match short_name {
+            Some(sn) => get_usage_data()?.into_iter().find(|u| u.filesystem.ends_with(sn)),
+            None => None,
+        };
+
+        PartitionModel::from_proxy(&client, self.drive_path.clone(), self.path.clone(), usage, &partition_proxy, &block_proxy).await
+    }
+
+
+    pub async fn format(&self, info: FormatPartitionInfo) -> Result<()>
     {
         if self.connection.is_none()
         {
             return Err(DiskError::NotConnected(self.name.clone()).into())
         }
 
+        info.validate().map_err(anyhow::Error::msg)?;
+
+        //A mounted filesystem can't be formatted, so unmount it first if it is.
+        let _ = self.unmount().await;
+
+        let proxy = BlockProxy::builder(&self.connection.as_ref().unwrap()).path( &self.path)?.build().await?;
+
+        proxy.format(info.filesystem.id(), info.options()).await?;
 
-        
         Ok(())
     }
 
 
-    //TODO: implement
-    pub async fn edit_partition(&self, partition_type: String, name: String, flags: u64) -> Result<()>
+    pub fn edit_info(&self, table_type: &str) -> EditPartitionInfo
+    {
+        EditPartitionInfo {
+            table_type: table_type.to_owned(),
+            type_id: self.type_id.clone(),
+            name: self.name.clone(),
+            flags: self.flags.bits(),
+            filter: String::new(),
+        }
+    }
+
+    pub async fn edit_partition(&self, info: EditPartitionInfo) -> Result<()>
     {
         if self.connection.is_none()
         {
             return Err(DiskError::NotConnected(self.name.clone()).into())
         }
 
+        let proxy = PartitionProxy::builder(&self.connection.as_ref().unwrap()).path( &self.path)?.build().await?;
+
+        if info.type_id != self.type_id
+        {
+            if !PartitionTypeInfo::for_table(&info.table_type).iter().any(|t| t.ty == info.type_id)
+            {
+                return Err(anyhow::anyhow!("{} is not a {} partition type", info.type_id, info.table_type));
+            }
+
+            proxy.set_type(&info.type_id, HashMap::new()).await?;
+        }
+
+        //Only GPT partitions have names.
+        if info.table_type == "gpt" && info.name != self.name
+        {
+            proxy.set_name(&info.name, HashMap::new()).await?;
+        }
+
+        if info.flags != self.flags.bits()
+        {
+            proxy.set_flags(info.flags, HashMap::new()).await?;
+        }
+
         Ok(())
     }
 
 
 
-    //TODO: implement
+    /// The filesystem's type, if it's one that can be edited.
+    pub fn filesystem(&self) -> Option<FilesystemType>
+    {
+        FilesystemType::from_id(&self.id_type)
+    }
+
+    /// Whether the block holds a filesystem that can be mounted, of any type, not only
+    /// the ones that can be formatted to.
+    pub fn has_filesystem(&self) -> bool
+    {
+        !matches!(self.id_type.as_str(), "" | "swap" | "crypto_LUKS" | "LVM2_member" | "linux_raid_member")
+    }
+
+    pub fn edit_filesystem_info(&self) -> Option<EditFilesystemInfo>
+    {
+        let filesystem = self.filesystem().filter(|f| f.supports_label_change())?;
+
+        Some(EditFilesystemInfo {
+            filesystem,
+            label: self.id_label.clone(),
+            uuid: self.id_uuid.clone(),
+            regenerate_uuid: false,
+        })
+    }
+
     pub async fn edit_filesystem_label(&self, label: String) -> Result<()>
     {
         if self.connection.is_none()
@@ -191,25 +370,49 @@ impl PartitionModel {
             return Err(DiskError::NotConnected(self.name.clone()).into())
         }
 
+        let label = match self.filesystem() {
+            Some(f) if f.supports_label_change() => f.validate_label(&label).map_err(anyhow::Error::msg)?,
+            _ => return Err(anyhow::anyhow!("The label of a {} filesystem can't be changed", self.id_type)),
+        };
+
+        let proxy = FilesystemProxy::builder(&self.connection.as_ref().unwrap()).path( &self.path)?.build().await?;
+
+        proxy.set_label(&label, HashMap::new()).await?;
 
-        
         Ok(())
     }
 
-
-    //TODO: implement
-    pub async fn change_passphrase(&self) ->Result<()>
+    /// Sets the filesystem's UUID, or a new random one if `uuid` is `None`.
+    pub async fn set_filesystem_uuid(&self, uuid: Option<String>) -> Result<()>
     {
         if self.connection.is_none()
         {
             return Err(DiskError::NotConnected(self.name.clone()).into())
         }
+
+        let filesystem = match self.filesystem() {
+            Some(f) if f.supports_uuid_change() => f,
+            _ => return Err(anyhow::anyhow!("The UUID of a {} filesystem can't be changed", self.id_type)),
+        };
+
+        let uuid = match uuid {
+            Some(uuid) => {
+                filesystem.validate_uuid(&uuid).map_err(anyhow::Error::msg)?;
+                uuid
+            }
+            None => filesystem.generate_uuid()?,
+        };
+
+        let proxy = FilesystemUuidProxy::builder(&self.connection.as_ref().unwrap()).path( &self.path)?.build().await?;
+
+        proxy.set_uuid(&uuid, HashMap::new()).await?;
+
         Ok(())
     }
 
 
     //TODO: implement
-    pub async fn resize(&self, new_size_bytes: u64) ->Result<()>
+    pub async fn change_passphrase(&self) ->Result<()>
     {
         if self.connection.is_none()
         {
@@ -219,59 +422,309 @@ impl PartitionModel {
     }
 
 
-    //TODO: implement
-    pub async fn check_filesystem(&self) ->Result<()>
+    //Partitions without a filesystem only have the partition itself to resize.
+    fn resize_mode(&self, grow: bool) -> Option<ResizeMode>
+    {
+        match self.filesystem() {
+            Some(f) => f.resize_mode(grow),
+            None if self.id_type.is_empty() => Some(ResizeMode::Any),
+            None => None,
+        }
+    }
+
+    /// How far the partition can be resized, given the free space right after it.
+    /// Shrinking stops short of the space in use, which is only known while mounted.
+    pub fn resize_info(&self, free_after: u64) -> ResizePartitionInfo
+    {
+        let max_size = match self.resize_mode(true) {
+            Some(_) => self.size + free_after,
+            None => self.size,
+        };
+
+        let min_size = match (self.resize_mode(false), &self.usage) {
+            (Some(_), Some(usage)) => {
+                let margin = self.filesystem().map(|f| f.shrink_margin(self.size)).unwrap_or_default();
+                (usage.used + margin).div_ceil(MIB) * MIB
+            }
+            (Some(_), None) if self.id_type.is_empty() => MIB,
+            _ => self.size,
+        };
+
+        ResizePartitionInfo {
+            size: self.size,
+            min_size: min_size.min(self.size),
+            max_size,
+        }
+    }
+
+    pub async fn resize(&self, new_size_bytes: u64) ->Result<()>
     {
         if self.connection.is_none()
         {
             return Err(DiskError::NotConnected(self.name.clone()).into())
         }
+
+        if new_size_bytes == self.size
+        {
+            return Ok(());
+        }
+
+        let grow = new_size_bytes > self.size;
+        let action = match grow {
+            true => "grown",
+            false => "shrunk",
+        };
+
+        let mode = match self.resize_mode(grow) {
+            Some(m) => m,
+            None => return Err(anyhow::anyhow!("{} filesystems can't be {}", self.id_type, action)),
+        };
+
+        let mounted = self.is_mounted();
+        let remount = match (mode, mounted) {
+            (ResizeMode::Online, false) => return Err(anyhow::anyhow!("{} filesystems can only be {} while mounted", self.id_type, action)),
+            (ResizeMode::Offline, true) => {
+                self.unmount().await?;
+                true
+            }
+            _ => false,
+        };
+
+        let connection = self.connection.as_ref().unwrap();
+        let partition_proxy = PartitionProxy::builder(connection).path( &self.path)?.build().await?;
+
+        let result = match self.filesystem() {
+            Some(f) if f != FilesystemType::Empty => {
+                let filesystem_proxy = FilesystemProxy::builder(connection).path( &self.path)?.build().await?;
+
+                //The filesystem has to fit inside the partition at every step.
+                match grow {
+                    true => match partition_proxy.resize(new_size_bytes, HashMap::new()).await {
+                        //A size of 0 fills the partition.
+                        Ok(_) => filesystem_proxy.resize(0, HashMap::new()).await,
+                        Err(e) => Err(e),
+                    },
+                    false => match filesystem_proxy.resize(new_size_bytes, HashMap::new()).await {
+                        Ok(_) => partition_proxy.resize(new_size_bytes, HashMap::new()).await,
+                        Err(e) => Err(e),
+                    },
+                }
+            }
+            _ => partition_proxy.resize(new_size_bytes, HashMap::new()).await,
+        };
+
+        //Remount even if the resize failed, but report the resize error first.
+        let remounted = match remount {
+            true => self.mount().await,
+            false => Ok(()),
+        };
+
+        result?;
+        remounted?;
+
         Ok(())
     }
 
 
-    //TODO: implement
-    pub async fn repair_filesystem(&self) ->Result<()>
+    pub fn can_check(&self) -> bool
+    {
+        match self.filesystem() {
+            Some(f) => f.can_check(),
+            //UDisks2 also checks filesystems that can't be formatted to here.
+            None => matches!(self.id_type.as_str(), "ext2" | "ext3" | "f2fs"),
+        }
+    }
+
+    pub async fn check_filesystem(&self) ->Result<CheckOutcome>
+    {
+        self.check_or_repair(false).await
+    }
+
+    pub async fn repair_filesystem(&self) ->Result<CheckOutcome>
+    {
+        self.check_or_repair(true).await
+    }
+
+    async fn check_or_repair(&self, repair: bool) -> Result<CheckOutcome>
     {
         if self.connection.is_none()
         {
             return Err(DiskError::NotConnected(self.name.clone()).into())
         }
-        Ok(())
+
+        if !self.can_check()
+        {
+            return Ok(CheckOutcome::Unsupported);
+        }
+
+        //Filesystems are checked unmounted, then put back the way they were.
+        let remount = self.is_mounted();
+        if remount
+        {
+            self.unmount().await?;
+        }
+
+        let proxy = FilesystemProxy::builder(&self.connection.as_ref().unwrap()).path( &self.path)?.build().await?;
+
+        //Check returns whether the filesystem is consistent, Repair whether it was fixed.
+        let result = match repair {
+            false => proxy.check(HashMap::new()).await.map(|clean| match clean {
+                true => CheckOutcome::Clean,
+                false => CheckOutcome::ErrorsFound,
+            }),
+            true => proxy.repair(HashMap::new()).await.map(|repaired| match repaired {
+                true => CheckOutcome::Repaired,
+                false => CheckOutcome::ErrorsFound,
+            }),
+        };
+
+        let remounted = match remount {
+            true => self.mount().await,
+            false => Ok(()),
+        };
+
+        let outcome = result?;
+        remounted?;
+
+        Ok(outcome)
     }
 
 
-    //TODO: implement
+    /// Whether the filesystem has Unix owners that can be taken over.
+    pub fn can_take_ownership(&self) -> bool
+    {
+        self.filesystem().is_some_and(|f| f.is_posix())
+    }
+
+    /// Makes the current user the owner of the filesystem's root, and everything in it if `recursive`.
     pub async fn take_ownership(&self, recursive: bool) ->Result<()>
     {
         if self.connection.is_none()
         {
             return Err(DiskError::NotConnected(self.name.clone()).into())
         }
+
+        if !self.can_take_ownership()
+        {
+            return Err(anyhow::anyhow!("{} filesystems don't have owners", self.id_type));
+        }
+
+        //Ownership can only be taken while mounted, so mount it for the duration if it isn't.
+        let unmount = !self.is_mounted();
+        if unmount
+        {
+            self.mount().await?;
+        }
+
+        let proxy = FilesystemProxy::builder(&self.connection.as_ref().unwrap()).path( &self.path)?.build().await?;
+
+        let result = proxy.take_ownership(HashMap::from([("recursive", Value::from(recursive))])).await;
+
+        let unmounted = match unmount {
+            true => self.unmount().await,
+            false => Ok(()),
+        };
+
+        result?;
+        unmounted?;
+
         Ok(())
     }
 
 
-    //TODO: implement. See how edit mount options -> User session defaults works in gnome-disks.
-    pub async fn default_mount_options(&self) -> Result<()>
+    async fn configuration_proxy(&self) -> Result<BlockConfigurationProxy<'static>>
     {
         if self.connection.is_none()
         {
             return Err(DiskError::NotConnected(self.name.clone()).into())
         }
-        Ok(())
+
+        Ok(BlockConfigurationProxy::builder(&self.connection.as_ref().unwrap()).path(self.path.clone())?.build().await?)
     }
 
+    async fn fstab_item(proxy: &BlockConfigurationProxy<'_>) -> Result<Option<ConfigurationItem>>
+    {
+        Ok(proxy.configuration().await?.into_iter().find(|(kind, _)| kind == "fstab"))
+    }
 
-    //TODO: implement. Look at gnome-disks -> partition -> edit mount options. Likely make all params optional.
-    pub async fn edit_mount_options(&self, mount_at_startup: bool, show_in_ui: bool, requre_auth: bool,display_name: Option<String>,
-                                    icon_name: Option<String>, symbolic_icon_name: Option<String>,  options: String,
-                                    mount_point: String, identify_as: String, file_system_type: String) ->Result<()>
+    /// The partition's fstab entry, or suggested options if it doesn't have one.
+    pub async fn mount_options(&self) -> Result<MountOptionsInfo>
     {
-        if self.connection.is_none()
+        let proxy = self.configuration_proxy().await?;
+
+        let mut identify_choices = vec![];
+        for (prefix, value) in [("UUID=", &self.id_uuid), ("LABEL=", &self.id_label), ("PARTUUID=", &self.uuid)] {
+            if !value.is_empty() {
+                identify_choices.push(format!("{}{}", prefix, value));
+            }
+        }
+        identify_choices.extend(self.device_path.clone());
+        identify_choices.extend(proxy.symlinks().await?.iter().map(|s| from_bytestring(s)));
+
+        let mut info = MountOptionsInfo {
+            identify_choices,
+            ..Default::default()
+        };
+
+        match Self::fstab_item(&proxy).await? {
+            Some(item) => {
+                info.identify_as = item_string(&item, "fsname");
+                info.mount_point = item_string(&item, "dir");
+                info.filesystem_type = item_string(&item, "type");
+                info.set_options(&item_string(&item, "opts"));
+
+                if !info.identify_choices.contains(&info.identify_as) {
+                    info.identify_choices.push(info.identify_as.clone());
+                }
+            }
+            None => {
+                let name = [&self.id_label, &self.id_uuid].into_iter().find(|n| !n.is_empty()).cloned().unwrap_or(self.pretty_name());
+
+                info.use_defaults = true;
+                info.identify_as = info.identify_choices.first().cloned().unwrap_or_default();
+                info.mount_point = format!("/mnt/{}", name.replace('/', "_"));
+                info.filesystem_type = match self.id_type.is_empty() {
+                    true => "auto".into(),
+                    false => self.id_type.clone(),
+                };
+                info.set_options("nosuid,nodev,nofail,x-gvfs-show");
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Removes the partition's fstab entry, leaving mounting to the user session.
+    pub async fn default_mount_options(&self) -> Result<()>
+    {
+        let proxy = self.configuration_proxy().await?;
+
+        if let Some(item) = Self::fstab_item(&proxy).await?
         {
-            return Err(DiskError::NotConnected(self.name.clone()).into())
+            proxy.remove_configuration_item(&item, HashMap::new()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the partition's fstab entry, or removes it if `info` uses the session defaults.
+    pub async fn edit_mount_options(&self, info: MountOptionsInfo) ->Result<()>
+    {
+        if info.use_defaults
+        {
+            return self.default_mount_options().await;
         }
+
+        info.validate().map_err(anyhow::Error::msg)?;
+
+        let proxy = self.configuration_proxy().await?;
+
+        match Self::fstab_item(&proxy).await?
+        {
+            Some(existing) => proxy.update_configuration_item(&existing, &info.to_item(Some(&existing))?, HashMap::new()).await?,
+            None => proxy.add_configuration_item(&info.to_item(None)?, HashMap::new()).await?,
+        }
+
         Ok(())
     }
 
@@ -70,9 +70,72 @@ impl PartitionTypeInfo {
         }
     }
 
+    /// The types a partition on a `table_type` table can be changed to.
+    pub fn for_table(table_type: &str) -> Vec<PartitionTypeInfo>
+    {
+        PARTITION_TYPES
+            .iter()
+            .filter(|p| p.table_type == table_type && !matches!(p.flags, PartitionTypeInfoFlags::CreateOnly))
+            .cloned()
+            .collect()
+    }
+
+    /// The common types a new partition on a `table_type` table can be created with.
+    pub fn common_for_table(table_type: &str) -> Vec<&'static PartitionTypeInfo>
+    {
+        //Extended partitions can't be formatted, so they're left out.
+        COMMON_PARTITION_TYPES
+            .iter()
+            .filter(|p| p.table_type == table_type && !matches!(p.flags, PartitionTypeInfoFlags::CreateOnly))
+            .collect()
+    }
+
+    /// Display names of `common_for_table`, in the same order.
+    pub fn common_names_for_table(table_type: &str) -> &'static [String]
+    {
+        match table_type {
+            "dos" => &COMMON_DOS_PARTITION_NAMES,
+            _ => &COMMON_GPT_PARTITION_NAMES,
+        }
+    }
+
+    pub fn display_name(&self) -> String
+    {
+        format!("{} - {}", self.name, self.ty)
+    }
+
 
 }
 
+/// A user-toggleable partition flag, as a bit of `Partition.Flags`.
+#[derive(Debug, Clone, Copy)]
+pub struct PartitionFlagInfo {
+    /// The partition table type the flag applies to, `dos` or `gpt`.
+    pub table_type: &'static str,
+    pub bit: u64,
+    pub name: &'static str,
+}
+
+impl PartitionFlagInfo {
+    pub fn for_table(table_type: &str) -> Vec<PartitionFlagInfo>
+    {
+        PARTITION_FLAGS
+            .iter()
+            .filter(|f| f.table_type == table_type)
+            .copied()
+            .collect()
+    }
+}
+
+pub static PARTITION_FLAGS: [PartitionFlagInfo; 6] = [
+    PartitionFlagInfo { table_type: "dos", bit: 0x80, name: "Bootable" },
+    PartitionFlagInfo { table_type: "gpt", bit: 1 << 0, name: "System partition" },
+    PartitionFlagInfo { table_type: "gpt", bit: 1 << 2, name: "Legacy BIOS bootable" },
+    PartitionFlagInfo { table_type: "gpt", bit: 1 << 60, name: "Read-only" },
+    PartitionFlagInfo { table_type: "gpt", bit: 1 << 62, name: "Hidden" },
+    PartitionFlagInfo { table_type: "gpt", bit: 1 << 63, name: "Do not automount" },
+];
+
 pub static PARTITION_NAMES: LazyLock<Vec<String>> = LazyLock::new(|| {
     PARTITION_TYPES
         .iter()
@@ -88,6 +151,19 @@ pub static COMMON_PARTITION_NAMES: LazyLock<Vec<String>> = LazyLock::new(|| {
 });
 
 
+pub static COMMON_GPT_PARTITION_NAMES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    PartitionTypeInfo::common_for_table("gpt")
+        .iter()
+        .map(|p| p.display_name())
+        .collect()
+});
+
+pub static COMMON_DOS_PARTITION_NAMES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    PartitionTypeInfo::common_for_table("dos")
+        .iter()
+        .map(|p| p.display_name())
+        .collect()
+});
 
 pub static COMMON_PARTITION_TYPES: [PartitionTypeInfo; 20] = [
     // System/Boot partitions
@@ -5,7 +5,7 @@ use crate::fl;
 use crate::views::about::about;
 use crate::views::dialogs;
 use crate::views::menu::{menu_view, MenuAction};
-use crate::views::volumes::{VolumesControl, VolumesControlMessage};
+use crate::views::volumes::{CheckMessage, FilesystemCheck, VolumesControl, VolumesControlMessage};
 use cosmic::app::{context_drawer, Core, Task};
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
 use cosmic::iced::alignment::{Horizontal, Vertical};
@@ -14,7 +14,10 @@ use cosmic::widget::text::heading;
 use cosmic::widget::{self, container, icon, menu, nav_bar, Space};
 use cosmic::{iced_widget, Application, ApplicationExt, Apply, Element};
 use cosmos_common::{bytes_to_pretty, labelled_info, link_info};
-use cosmos_dbus::disks::{CreatePartitionInfo, DiskManager, DriveModel};
+use cosmos_dbus::disks::{
+    CreatePartitionInfo, DeviceChange, DeviceEvent, DeviceKind, DiskManager, DriveModel, EditFilesystemInfo, EditPartitionInfo,
+    FormatPartitionInfo, MountOptionsInfo, PartitionModel, ResizePartitionInfo,
+};
 use futures_util::{SinkExt, StreamExt};
 use std::collections::HashMap;
 use std::time::Duration;
@@ -43,6 +46,16 @@ pub struct AppModel {
 pub enum ShowDialog {
     DeletePartition(String),
     AddPartition(CreatePartitionInfo),
+    FormatPartition(FormatPartitionInfo),
+    EditPartition(EditPartitionInfo),
+    EditFilesystem(EditFilesystemInfo),
+    ResizePartition(ResizePartitionInfo),
+    CheckFilesystem(FilesystemCheck),
+    /// Asks before repairing the named filesystem.
+    RepairFilesystem(String),
+    /// Whether to take ownership recursively.
+    TakeOwnership(bool),
+    MountOptions(MountOptionsInfo),
 }
 
 /// Messages emitted by the application and its widgets.
@@ -56,6 +69,9 @@ pub enum Message {
     VolumesMessage(VolumesControlMessage),
     DriveRemoved(String),
     DriveAdded(String),
+    DeviceChanged(DeviceChange),
+    DriveUpdated(DriveModel),
+    PartitionUpdated(PartitionModel),
     None,
     UpdateNav(Vec<DriveModel>, Option<String>),
     Dialog(ShowDialog),
@@ -158,6 +174,22 @@ impl Application for AppModel {
                 )),
 
                 ShowDialog::AddPartition(create) => Some(dialogs::create_partition(create.clone())),
+                ShowDialog::FormatPartition(format) => Some(dialogs::format_partition(format.clone())),
+                ShowDialog::EditPartition(edit) => Some(dialogs::edit_partition(edit.clone())),
+                ShowDialog::EditFilesystem(edit) => Some(dialogs::edit_filesystem(edit.clone())),
+                ShowDialog::ResizePartition(resize) => Some(dialogs::resize_partition(resize.clone())),
+                ShowDialog::CheckFilesystem(check) => Some(dialogs::check_filesystem(check.clone())),
+                ShowDialog::RepairFilesystem(name) => Some(dialogs::confirmation(
+                    format!("Repair {}", name),
+                    format!(
+                        "{} will be unmounted and its filesystem modified to fix errors. Are you sure you wish to repair it?",
+                        name
+                    ),
+                    CheckMessage::Start(true).into(),
+                    Some(Message::CloseDialog),
+                )),
+                ShowDialog::TakeOwnership(recursive) => Some(dialogs::take_ownership(*recursive)),
+                ShowDialog::MountOptions(info) => Some(dialogs::mount_options(info)),
             },
             None => None,
         }
@@ -350,12 +382,19 @@ impl Application for AppModel {
 
                     while let Some(event) = stream.next().await {
                         match event {
-                            cosmos_dbus::disks::DeviceEvent::Added(s) => {
+                            // Jobs don't change the layout; whatever they do shows up as
+                            // devices coming and going.
+                            DeviceEvent::Added(DeviceKind::Job, _)
+                            | DeviceEvent::Removed(DeviceKind::Job, _) => {}
+                            DeviceEvent::Added(_, s) => {
                                 let _ = c.send(Message::DriveAdded(s)).await;
                             }
-                            cosmos_dbus::disks::DeviceEvent::Removed(s) => {
+                            DeviceEvent::Removed(_, s) => {
                                 let _ = c.send(Message::DriveRemoved(s)).await;
                             }
+                            DeviceEvent::Changed(change) => {
+                                let _ = c.send(Message::DeviceChanged(change)).await;
+                            }
                         }
                     }
                 }),
@@ -411,24 +450,76 @@ impl Application for AppModel {
                 let volumes_control = self.nav.active_data_mut::<VolumesControl>().unwrap(); //TODO: HANDLE UNWRAP.
                 return volumes_control.update(message, &mut self.dialog);
             }
-            Message::DriveRemoved(_drive_model) => {
-                //TODO: use DeviceManager.apply_change()
-
-                return Task::perform(
-                    async {
-                        match DriveModel::get_drives().await {
-                            Ok(drives) => Some(drives),
-                            Err(e) => {
-                                println!("Error: {}", e);
-                                return None;
+            Message::DriveRemoved(path) => {
+                if let Some(id) = self.drive_entity(|d| d.path == path || d.block_path == path) {
+                    let was_active = self.nav.active() == id;
+                    self.nav.remove(id);
+
+                    if was_active {
+                        if let Some(first) = self.nav.iter().next() {
+                            self.nav.activate(first);
+                        }
+                        return self.update_title();
+                    }
+                } else if let Some(id) = self.drive_entity(|d| d.partition(&path).is_some()) {
+                    if let Some(mut drive) = self.nav.data::<DriveModel>(id).cloned() {
+                        drive.partitions.retain(|p| p.path.as_str() != path);
+                        self.patch_drive(id, drive);
+                    }
+                }
+            }
+            Message::DeviceChanged(change) => {
+                let path = change.path().to_string();
+
+                //Show the new mount state right away; the reload below catches up on usage.
+                if let DeviceChange::MountPoints(_, mount_points) = &change {
+                    if let Some(id) = self.drive_entity(|d| d.partition(&path).is_some()) {
+                        if let Some(mut drive) = self.nav.data::<DriveModel>(id).cloned() {
+                            if let Some(p) = drive.partitions.iter_mut().find(|p| p.path.as_str() == path) {
+                                p.mount_points = mount_points.clone();
                             }
+                            self.patch_drive(id, drive);
                         }
-                    },
-                    move |drives| match drives {
-                        None => return Message::None.into(),
-                        Some(drives) => return Message::UpdateNav(drives, None).into(),
-                    },
-                );
+                    }
+                }
+
+                if let Some(id) = self.drive_entity(|d| d.path == path || d.block_path == path) {
+                    let drive = self.nav.data::<DriveModel>(id).cloned().unwrap();
+                    return Task::perform(async move { drive.reload().await }, |result| match result {
+                        Ok(drive) => Message::DriveUpdated(drive).into(),
+                        Err(e) => {
+                            println!("Error reloading drive: {}", e);
+                            Message::None.into()
+                        }
+                    });
+                }
+
+                if let Some(id) = self.drive_entity(|d| d.partition(&path).is_some()) {
+                    let partition = self.nav.data::<DriveModel>(id).and_then(|d| d.partition(&path)).cloned().unwrap();
+                    return Task::perform(async move { partition.reload().await }, |result| match result {
+                        Ok(partition) => Message::PartitionUpdated(partition).into(),
+                        Err(e) => {
+                            println!("Error reloading partition: {}", e);
+                            Message::None.into()
+                        }
+                    });
+                }
+            }
+            Message::DriveUpdated(drive) => {
+                if let Some(id) = self.drive_entity(|d| d.path == drive.path) {
+                    self.patch_drive(id, drive);
+                }
+            }
+            Message::PartitionUpdated(partition) => {
+                if let Some(id) = self.drive_entity(|d| d.path == partition.drive_path) {
+                    if let Some(mut drive) = self.nav.data::<DriveModel>(id).cloned() {
+                        match drive.partitions.iter_mut().find(|p| p.path == partition.path) {
+                            Some(p) => *p = partition,
+                            None => drive.partitions.push(partition),
+                        }
+                        self.patch_drive(id, drive);
+                    }
+                }
             }
             Message::DriveAdded(_drive_model) => {
 
@@ -450,6 +541,11 @@ impl Application for AppModel {
             }
             Message::None => {}
             Message::UpdateNav(drive_models, selected) => {
+                // Keep the same segment selected when the same drive stays selected.
+                let selected_offset = match selected {
+                    Some(_) => None,
+                    None => self.nav.active_data::<VolumesControl>().and_then(|v| v.selected_offset()),
+                };
                 let selected = match selected {
                     Some(s) => Some(s),
                     None => match self.nav.active_data::<DriveModel>() {
@@ -506,6 +602,12 @@ impl Application for AppModel {
                         }
                     }
                 }
+
+                if let (Some(offset), Some(volumes_control)) =
+                    (selected_offset, self.nav.active_data_mut::<VolumesControl>())
+                {
+                    volumes_control.select_offset(offset);
+                }
             }
             Message::Dialog(show_dialog) => self.dialog = Some(show_dialog),
             Message::CloseDialog => {
@@ -513,21 +615,14 @@ impl Application for AppModel {
             }
             Message::Eject => {
                 if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    // The drive's media going away is picked up by the device event subscription.
                     return Task::perform(
                         async move {
-                            let _ = drive.eject().await; //TODO handle error
-                            match DriveModel::get_drives().await {
-                                Ok(drives) => Some(drives),
-                                Err(e) => {
-                                    println!("Error: {}", e);
-                                    return None;
-                                }
+                            if let Err(e) = drive.eject().await {
+                                println!("Error: {}", e);
                             }
                         },
-                        move |drives| match drives {
-                            None => return Message::None.into(),
-                            Some(drives) => return Message::UpdateNav(drives, None).into(),
-                        },
+                        |_| Message::None.into(),
                     );
                 } else {
                 }
@@ -560,6 +655,24 @@ impl Application for AppModel {
 }
 
 impl AppModel {
+    /// The nav item holding the first drive that matches `predicate`.
+    fn drive_entity(&self, predicate: impl Fn(&DriveModel) -> bool) -> Option<nav_bar::Id> {
+        self.nav
+            .iter()
+            .find(|id| self.nav.data::<DriveModel>(*id).is_some_and(|d| predicate(d)))
+    }
+
+    /// Replaces a drive's model in place, leaving the selected drive and segment as they were.
+    fn patch_drive(&mut self, id: nav_bar::Id, drive: DriveModel) {
+        self.nav.text_set(id, drive.pretty_name());
+
+        if let Some(volumes_control) = self.nav.data_mut::<VolumesControl>(id) {
+            volumes_control.set_model(drive.clone());
+        }
+
+        self.nav.data_set::<DriveModel>(id, drive);
+    }
+
     /// The about page for this app.
     /// Updates the header and window titles.
     pub fn update_title(&mut self) -> Task<Message> {
@@ -1,15 +1,22 @@
-use super::volumes::CreateMessage;
-use crate::app::Message;
+use super::volumes::{
+    CheckMessage, CreateMessage, EditMessage, FilesystemCheck, FilesystemMessage, FormatMessage, MountOptionsMessage,
+    OwnershipMessage, ResizeMessage,
+};
+use crate::app::{Message, ShowDialog};
 use cosmic::{
     iced::Length,
     iced_widget,
     widget::{
-        button, checkbox, container, dialog, dropdown, slider, spin_button, text_input, toggler
+        button, checkbox, container, dialog, dropdown, scrollable, slider, spin_button, text, text_input, toggler
     },
     Element,
 };
 use cosmos_common::{bytes_to_pretty, labelled_spinner};
-use cosmos_dbus::disks::{CreatePartitionInfo, COMMON_PARTITION_NAMES, PARTITION_NAMES};
+use cosmos_dbus::disks::{
+    CheckOutcome, CreatePartitionInfo, EditFilesystemInfo, EditPartitionInfo, EraseMode, FilesystemType,
+    FormatPartitionInfo, MountOptionsInfo, PartitionFlagInfo, PartitionTypeInfo, ResizePartitionInfo,
+    FILESYSTEM_NAMES, PARTITION_NAMES,
+};
 use std::borrow::Cow;
 
 pub fn confirmation<'a>(
@@ -67,15 +74,22 @@ pub fn create_partition<'a>(create: CreatePartitionInfo) -> Element<'a, Message>
             .label("Erase")
             .on_toggle(|v| CreateMessage::EraseUpdate(v).into()),
         dropdown(
-            &COMMON_PARTITION_NAMES,
+            PartitionTypeInfo::common_names_for_table(&create.table_type),
             Some(create_clone.selected_partitition_type),
             |v| CreateMessage::PartitionTypeUpdate(v).into()
         ),
-        checkbox("Password Protected", create.password_protected)
-            .on_toggle(|v| CreateMessage::PasswordProectedUpdate(v).into()),
-       
+        dropdown(
+            &FILESYSTEM_NAMES,
+            FilesystemType::ALL.iter().position(|f| *f == create_clone.filesystem),
+            |v| CreateMessage::FilesystemUpdate(v).into()
+        ),
     ];
 
+    if create.filesystem != FilesystemType::Empty {
+        content = content.push(checkbox("Password Protected", create.password_protected)
+            .on_toggle(|v| CreateMessage::PasswordProectedUpdate(v).into()));
+    }
+
     
     if create.password_protected
     {
@@ -92,10 +106,11 @@ pub fn create_partition<'a>(create: CreatePartitionInfo) -> Element<'a, Message>
 
     let mut continue_button = button::destructive("Continue");
 
-    // if create.can_continue
-    //{
-    continue_button = continue_button.on_press(CreateMessage::Partition(create).into());
-    //}
+    match create.validate() {
+        Ok(()) if create.can_continue => continue_button = continue_button.on_press(CreateMessage::Partition(create).into()),
+        Ok(()) => {}
+        Err(e) => content = content.push(text::caption(e)),
+    }
 
     dialog::dialog()
         .title("Create Partition")
@@ -104,3 +119,302 @@ pub fn create_partition<'a>(create: CreatePartitionInfo) -> Element<'a, Message>
         .secondary_action(button::standard("Cancel").on_press(CreateMessage::Cancel.into()))
         .into()
 }
+
+pub fn format_partition<'a>(format: FormatPartitionInfo) -> Element<'a, Message> {
+    let format_clone = format.clone();
+    let selected = FilesystemType::ALL.iter().position(|f| *f == format.filesystem);
+
+    let mut content = iced_widget::column![dropdown(&FILESYSTEM_NAMES, selected, |v| {
+        FormatMessage::FilesystemUpdate(v).into()
+    })];
+
+    if format.filesystem != FilesystemType::Empty {
+        content = content.push(
+            text_input("Volume name", format_clone.name)
+                .label("Volume Name")
+                .on_input(|t| FormatMessage::NameUpdate(t).into()),
+        );
+    }
+
+    content = content.push(
+        toggler(format.erase == EraseMode::Zero)
+            .label("Overwrite existing data with zeroes (slow)")
+            .on_toggle(|v| FormatMessage::EraseUpdate(v).into()),
+    );
+
+    if format.filesystem.is_posix() {
+        content = content.push(
+            checkbox("Take ownership", format.take_ownership)
+                .on_toggle(|v| FormatMessage::TakeOwnershipUpdate(v).into()),
+        );
+    }
+
+    if format.filesystem != FilesystemType::Empty {
+        content = content.push(
+            checkbox("Password Protected", format.password_protected)
+                .on_toggle(|v| FormatMessage::PasswordProtectedUpdate(v).into()),
+        );
+    }
+
+    if format.password_protected
+    {
+        content = content.push( text_input::secure_input("", format_clone.password, None, true)
+        .label("Password")
+        .on_input(|v| FormatMessage::PasswordUpdate(v).into()));
+
+        content = content.push(  text_input::secure_input("", format_clone.confirmed_password, None, true)
+        .label("Confirm")
+        .on_input(|v| FormatMessage::ConfirmedPasswordUpdate(v).into()));
+    }
+
+    let mut format_button = button::destructive("Format");
+
+    match format.validate() {
+        Ok(()) => format_button = format_button.on_press(FormatMessage::Format(format).into()),
+        Err(e) => content = content.push(text::caption(e)),
+    }
+
+    dialog::dialog()
+        .title("Format Partition")
+        .body("All data on the partition will be lost.")
+        .control(content.spacing(20.))
+        .primary_action(format_button)
+        .secondary_action(button::standard("Cancel").on_press(FormatMessage::Cancel.into()))
+        .into()
+}
+
+pub fn edit_partition<'a>(edit: EditPartitionInfo) -> Element<'a, Message> {
+    let filter = edit.filter.to_lowercase();
+
+    let types: Vec<Element<'a, Message>> = PartitionTypeInfo::for_table(&edit.table_type)
+        .into_iter()
+        .filter(|t| t.display_name().to_lowercase().contains(&filter))
+        .map(|t| {
+            let name = t.display_name();
+            let type_id = t.ty.to_owned();
+            let type_button = match type_id == edit.type_id {
+                true => button::suggested(name),
+                false => button::text(name),
+            };
+
+            type_button
+                .on_press(EditMessage::TypeSelected(type_id).into())
+                .width(Length::Fill)
+                .into()
+        })
+        .collect();
+
+    let mut content = iced_widget::column![
+        text_input("Search partition types", edit.filter.clone())
+            .label("Type")
+            .on_input(|t| EditMessage::FilterUpdate(t).into()),
+        scrollable(iced_widget::Column::from_vec(types).spacing(5)).height(Length::Fixed(200.)),
+    ];
+
+    //DOS partitions don't have names.
+    if edit.table_type == "gpt" {
+        content = content.push(
+            text_input("Partition name", edit.name.clone())
+                .label("Name")
+                .on_input(|t| EditMessage::NameUpdate(t).into()),
+        );
+    }
+
+    for flag in PartitionFlagInfo::for_table(&edit.table_type) {
+        content = content.push(
+            checkbox(flag.name, edit.flags & flag.bit != 0)
+                .on_toggle(move |v| EditMessage::FlagToggled(flag.bit, v).into()),
+        );
+    }
+
+    dialog::dialog()
+        .title("Edit Partition")
+        .control(content.spacing(20.))
+        .primary_action(button::suggested("Apply").on_press(EditMessage::Apply(edit).into()))
+        .secondary_action(button::standard("Cancel").on_press(EditMessage::Cancel.into()))
+        .into()
+}
+
+pub fn edit_filesystem<'a>(edit: EditFilesystemInfo) -> Element<'a, Message> {
+    let edit_clone = edit.clone();
+
+    let mut content = iced_widget::column![text_input("Label", edit_clone.label)
+        .label("Label")
+        .on_input(|t| FilesystemMessage::LabelUpdate(t).into())];
+
+    if edit.filesystem.supports_uuid_change() {
+        content = content.push(
+            checkbox("Generate a new UUID", edit.regenerate_uuid)
+                .on_toggle(|v| FilesystemMessage::RegenerateUuidUpdate(v).into()),
+        );
+
+        if !edit.regenerate_uuid {
+            content = content.push(
+                text_input("UUID", edit_clone.uuid)
+                    .label("UUID")
+                    .on_input(|t| FilesystemMessage::UuidUpdate(t).into()),
+            );
+        }
+    }
+
+    let mut apply_button = button::suggested("Apply");
+
+    match edit.validate() {
+        Ok(()) => apply_button = apply_button.on_press(FilesystemMessage::Apply(edit).into()),
+        Err(e) => content = content.push(text::caption(e)),
+    }
+
+    dialog::dialog()
+        .title("Edit Filesystem")
+        .control(content.spacing(20.))
+        .primary_action(apply_button)
+        .secondary_action(button::standard("Cancel").on_press(FilesystemMessage::Cancel.into()))
+        .into()
+}
+
+pub fn resize_partition<'a>(resize: ResizePartitionInfo) -> Element<'a, Message> {
+    let min = resize.min_size as f64;
+    let max = resize.max_size as f64;
+    let size = resize.size as f64;
+    let free = max - size;
+
+    let step = cosmos_common::get_step(&resize.max_size);
+
+    let content = iced_widget::column![
+        slider((min..=max), size, |v| ResizeMessage::SizeUpdate(v as u64).into()),
+        labelled_spinner("Partition Size", bytes_to_pretty(&resize.size, false), size, step, min, max, |v| {
+            ResizeMessage::SizeUpdate(v as u64).into()
+        }),
+        labelled_spinner("Free Space Following", bytes_to_pretty(&(free as u64), false), free, step, 0., max - min, move |v| {
+            ResizeMessage::SizeUpdate((max - v) as u64).into()
+        }),
+        text::caption(format!(
+            "Can be resized between {} and {}",
+            bytes_to_pretty(&resize.min_size, false),
+            bytes_to_pretty(&resize.max_size, false)
+        )),
+    ];
+
+    dialog::dialog()
+        .title("Resize Partition")
+        .control(content.spacing(20.))
+        .primary_action(button::suggested("Resize").on_press(ResizeMessage::Resize(resize).into()))
+        .secondary_action(button::standard("Cancel").on_press(ResizeMessage::Cancel.into()))
+        .into()
+}
+
+pub fn check_filesystem<'a>(check: FilesystemCheck) -> Element<'a, Message> {
+    let (title, running) = match check.repair {
+        true => (format!("Repairing {}", check.name), "Repairing the filesystem. This may take a while."),
+        false => (format!("Checking {}", check.name), "Checking the filesystem. This may take a while."),
+    };
+
+    let mut dialog = dialog::dialog().title(title);
+
+    match check.outcome {
+        None => {
+            dialog = dialog
+                .body(running)
+                .primary_action(button::standard("Close"));
+        }
+        Some(outcome) => {
+            let offer_repair = outcome == Ok(CheckOutcome::ErrorsFound) && !check.repair;
+
+            let summary = match outcome {
+                Ok(CheckOutcome::Clean) => "No problems were found.".to_owned(),
+                Ok(CheckOutcome::ErrorsFound) if check.repair => "The filesystem could not be repaired.".to_owned(),
+                Ok(CheckOutcome::ErrorsFound) => "The filesystem has errors. Repairing it may fix them.".to_owned(),
+                Ok(CheckOutcome::Repaired) => "The filesystem was repaired.".to_owned(),
+                Ok(CheckOutcome::Unsupported) => "This filesystem can't be checked or repaired.".to_owned(),
+                Err(e) => format!("The check failed: {}", e),
+            };
+
+            dialog = dialog
+                .body(summary)
+                .primary_action(button::standard("Close").on_press(CheckMessage::Close.into()));
+
+            if offer_repair {
+                dialog = dialog.secondary_action(button::destructive("Repair").on_press(Message::Dialog(ShowDialog::RepairFilesystem(check.name.clone()))));
+            }
+        }
+    }
+
+    dialog.into()
+}
+
+pub fn take_ownership<'a>(recursive: bool) -> Element<'a, Message> {
+    dialog::dialog()
+        .title("Take Ownership")
+        .body("Make your user the owner of this filesystem, so you can write to it without administrator rights.")
+        .control(
+            checkbox("Apply to all files and folders", recursive)
+                .on_toggle(|v| OwnershipMessage::RecursiveUpdate(v).into()),
+        )
+        .primary_action(button::suggested("Take Ownership").on_press(OwnershipMessage::Apply(recursive).into()))
+        .secondary_action(button::standard("Cancel").on_press(OwnershipMessage::Cancel.into()))
+        .into()
+}
+
+pub fn mount_options<'a>(info: &'a MountOptionsInfo) -> Element<'a, Message> {
+    let mut content = iced_widget::column![toggler(info.use_defaults)
+        .label("User Session Defaults")
+        .on_toggle(|v| MountOptionsMessage::UseDefaultsUpdate(v).into())];
+
+    if !info.use_defaults {
+        content = content
+            .push(checkbox("Mount at system startup", info.mount_at_startup)
+                .on_toggle(|v| MountOptionsMessage::MountAtStartupUpdate(v).into()))
+            .push(checkbox("Show in user interface", info.show_in_ui)
+                .on_toggle(|v| MountOptionsMessage::ShowInUiUpdate(v).into()))
+            .push(checkbox("Require additional authorization to mount", info.require_auth)
+                .on_toggle(|v| MountOptionsMessage::RequireAuthUpdate(v).into()));
+
+        if info.show_in_ui {
+            content = content
+                .push(text_input("", info.display_name.clone())
+                    .label("Display Name")
+                    .on_input(|t| MountOptionsMessage::DisplayNameUpdate(t).into()))
+                .push(text_input("", info.icon_name.clone())
+                    .label("Icon Name")
+                    .on_input(|t| MountOptionsMessage::IconNameUpdate(t).into()))
+                .push(text_input("", info.symbolic_icon_name.clone())
+                    .label("Symbolic Icon Name")
+                    .on_input(|t| MountOptionsMessage::SymbolicIconNameUpdate(t).into()));
+        }
+
+        content = content
+            .push(text_input("nosuid,nodev,nofail", info.options.clone())
+                .label("Mount Options")
+                .on_input(|t| MountOptionsMessage::OptionsUpdate(t).into()))
+            .push(text_input("", info.mount_point.clone())
+                .label("Mount Point")
+                .on_input(|t| MountOptionsMessage::MountPointUpdate(t).into()))
+            .push(dropdown(
+                &info.identify_choices,
+                info.identify_choices.iter().position(|c| *c == info.identify_as),
+                |v| MountOptionsMessage::IdentifyAsUpdate(v).into(),
+            ))
+            .push(text_input("auto", info.filesystem_type.clone())
+                .label("Filesystem Type")
+                .on_input(|t| MountOptionsMessage::FilesystemTypeUpdate(t).into()));
+    }
+
+    let mut apply_button = button::suggested("Apply");
+
+    match info.validate() {
+        Ok(()) => {
+            if !info.use_defaults {
+                content = content.push(text::monotext(info.fstab_line()));
+            }
+            apply_button = apply_button.on_press(MountOptionsMessage::Apply(info.clone()).into());
+        }
+        Err(e) => content = content.push(text::caption(e)),
+    }
+
+    dialog::dialog()
+        .title("Mount Options")
+        .control(scrollable(content.spacing(20.)).height(Length::Fixed(400.)))
+        .primary_action(apply_button)
+        .secondary_action(button::standard("Cancel").on_press(MountOptionsMessage::Cancel.into()))
+        .into()
+}
@@ -9,7 +9,10 @@ use cosmic::{
     Element, Task,
 };
 use cosmos_common::bytes_to_pretty;
-use cosmos_dbus::disks::{CreatePartitionInfo, DriveModel, PartitionModel};
+use cosmos_dbus::disks::{
+    CheckOutcome, CreatePartitionInfo, DriveModel, EditFilesystemInfo, EditPartitionInfo, EraseMode, FilesystemType,
+    FormatPartitionInfo, MountOptionsInfo, PartitionModel, ResizePartitionInfo,
+};
 use crate::app::{Message, ShowDialog};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -18,7 +21,14 @@ pub enum VolumesControlMessage {
     Mount,
     Unmount,
     Delete,
-    CreateMessage(CreateMessage)
+    CreateMessage(CreateMessage),
+    FormatMessage(FormatMessage),
+    EditMessage(EditMessage),
+    FilesystemMessage(FilesystemMessage),
+    ResizeMessage(ResizeMessage),
+    CheckMessage(CheckMessage),
+    OwnershipMessage(OwnershipMessage),
+    MountOptionsMessage(MountOptionsMessage),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -31,11 +41,151 @@ pub enum CreateMessage
     PasswordProectedUpdate(bool),
     EraseUpdate(bool),
     PartitionTypeUpdate(usize),
+    FilesystemUpdate(usize),
     Continue,
     Cancel,
     Partition(CreatePartitionInfo)
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatMessage
+{
+    NameUpdate(String),
+    FilesystemUpdate(usize),
+    EraseUpdate(bool),
+    TakeOwnershipUpdate(bool),
+    PasswordProtectedUpdate(bool),
+    PasswordUpdate(String),
+    ConfirmedPasswordUpdate(String),
+    Cancel,
+    Format(FormatPartitionInfo),
+}
+
+impl Into<Message> for FormatMessage
+{
+    fn into(self) -> Message {
+        Message::VolumesMessage(VolumesControlMessage::FormatMessage(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditMessage
+{
+    FilterUpdate(String),
+    TypeSelected(String),
+    NameUpdate(String),
+    FlagToggled(u64, bool),
+    Cancel,
+    Apply(EditPartitionInfo),
+}
+
+impl Into<Message> for EditMessage
+{
+    fn into(self) -> Message {
+        Message::VolumesMessage(VolumesControlMessage::EditMessage(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilesystemMessage
+{
+    LabelUpdate(String),
+    UuidUpdate(String),
+    RegenerateUuidUpdate(bool),
+    Cancel,
+    Apply(EditFilesystemInfo),
+}
+
+impl Into<Message> for FilesystemMessage
+{
+    fn into(self) -> Message {
+        Message::VolumesMessage(VolumesControlMessage::FilesystemMessage(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResizeMessage
+{
+    SizeUpdate(u64),
+    Cancel,
+    Resize(ResizePartitionInfo),
+}
+
+impl Into<Message> for ResizeMessage
+{
+    fn into(self) -> Message {
+        Message::VolumesMessage(VolumesControlMessage::ResizeMessage(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckMessage
+{
+    /// Check the selected filesystem, or repair it if true.
+    Start(bool),
+    Finished(Result<CheckOutcome, String>),
+    Close,
+}
+
+impl Into<Message> for CheckMessage
+{
+    fn into(self) -> Message {
+        Message::VolumesMessage(VolumesControlMessage::CheckMessage(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OwnershipMessage
+{
+    RecursiveUpdate(bool),
+    Cancel,
+    /// Take ownership, of everything on the filesystem if true.
+    Apply(bool),
+}
+
+impl Into<Message> for OwnershipMessage
+{
+    fn into(self) -> Message {
+        Message::VolumesMessage(VolumesControlMessage::OwnershipMessage(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MountOptionsMessage
+{
+    Open,
+    Loaded(MountOptionsInfo),
+    UseDefaultsUpdate(bool),
+    MountAtStartupUpdate(bool),
+    ShowInUiUpdate(bool),
+    RequireAuthUpdate(bool),
+    DisplayNameUpdate(String),
+    IconNameUpdate(String),
+    SymbolicIconNameUpdate(String),
+    OptionsUpdate(String),
+    MountPointUpdate(String),
+    IdentifyAsUpdate(usize),
+    FilesystemTypeUpdate(String),
+    Cancel,
+    Apply(MountOptionsInfo),
+}
+
+impl Into<Message> for MountOptionsMessage
+{
+    fn into(self) -> Message {
+        Message::VolumesMessage(VolumesControlMessage::MountOptionsMessage(self))
+    }
+}
+
+/// A filesystem check or repair, running while `outcome` is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilesystemCheck
+{
+    pub name: String,
+    pub repair: bool,
+    pub outcome: Option<Result<CheckOutcome, String>>,
+}
+
 impl Into<VolumesControlMessage> for CreateMessage
 {
     fn into(self) -> VolumesControlMessage {
@@ -112,12 +262,29 @@ impl Segment {
         }
     }
 
-    pub fn get_create_info(&self) -> CreatePartitionInfo
+    pub fn get_create_info(&self, table_type: &str) -> CreatePartitionInfo
     {
-        CreatePartitionInfo{
+        let mut info = CreatePartitionInfo{
             max_size: self.size,
             offset: self.offset,
             size: self.size,
+            table_type: table_type.to_owned(),
+            ..Default::default()
+        };
+        //DOS tables list the protective EFI GPT type first; start from plain Linux instead.
+        if table_type == "dos" {
+            info.selected_partitition_type = info.partition_types().iter().position(|t| t.ty == "0x83").unwrap_or_default();
+        }
+        info.can_continue = info.validate().is_ok();
+        info
+    }
+
+    pub fn get_format_info(&self) -> FormatPartitionInfo
+    {
+        let filesystem = self.partition.as_ref().and_then(|p| FilesystemType::from_id(&p.id_type));
+
+        FormatPartitionInfo{
+            filesystem: filesystem.unwrap_or_default(),
             ..Default::default()
         }
     }
@@ -238,6 +405,35 @@ impl VolumesControl {
         }
     }
 
+    /// Swaps in an updated drive, keeping the segment at the same offset selected if it's still there.
+    pub fn set_model(&mut self, model: DriveModel) {
+        let selected_offset = self.selected_offset();
+
+        self.segments = Segment::get_segments(&model);
+        self.model = model;
+        self.selected_segment = 0;
+
+        if let Some(offset) = selected_offset {
+            self.select_offset(offset);
+        }
+
+        if let Some(segment) = self.segments.get_mut(self.selected_segment) {
+            segment.state = true;
+        }
+    }
+
+    /// The offset of the selected segment, to find it again after the segments are rebuilt.
+    pub fn selected_offset(&self) -> Option<u64> {
+        self.segments.get(self.selected_segment).map(|s| s.offset)
+    }
+
+    pub fn select_offset(&mut self, offset: u64) {
+        if let Some(index) = self.segments.iter().position(|s| s.offset == offset) {
+            self.selected_segment = index;
+            self.segments.iter_mut().enumerate().for_each(|(i, s)| s.state = i == index);
+        }
+    }
+
     pub fn update(
         &mut self,
         message: VolumesControlMessage,
@@ -261,15 +457,12 @@ impl VolumesControl {
                         return Task::perform(
                             async move {
                                 match p.mount().await {
-                                    Ok(_) => match DriveModel::get_drives().await {
-                                        Ok(drives) => Ok(drives),
-                                        Err(e) => Err(e),
-                                    },
+                                    Ok(_) => p.reload().await,
                                     Err(e) => Err(e),
                                 }
                             },
                             |result| match result {
-                                Ok(drives) => Message::UpdateNav(drives, None).into(),
+                                Ok(partition) => Message::PartitionUpdated(partition).into(),
                                 Err(e) => {
                                     println!("{e}");
                                     Message::None.into()
@@ -291,15 +484,12 @@ impl VolumesControl {
                         return Task::perform(
                             async move {
                                 match p.unmount().await {
-                                    Ok(_) => match DriveModel::get_drives().await {
-                                        Ok(drives) => Ok(drives),
-                                        Err(e) => Err(e),
-                                    },
+                                    Ok(_) => p.reload().await,
                                     Err(e) => Err(e),
                                 }
                             },
                             |result| match result {
-                                Ok(drives) => Message::UpdateNav(drives, None).into(),
+                                Ok(partition) => Message::PartitionUpdated(partition).into(),
                                 Err(e) => {
                                     println!("{e}");
                                     Message::None.into()
@@ -351,7 +541,15 @@ impl VolumesControl {
             };
 
             match d{
-                ShowDialog::DeletePartition(_) => {},
+                ShowDialog::DeletePartition(_)
+                | ShowDialog::FormatPartition(_)
+                | ShowDialog::EditPartition(_)
+                | ShowDialog::EditFilesystem(_)
+                | ShowDialog::ResizePartition(_)
+                | ShowDialog::CheckFilesystem(_)
+                | ShowDialog::RepairFilesystem(_)
+                | ShowDialog::TakeOwnership(_)
+                | ShowDialog::MountOptions(_) => {},
 
                 ShowDialog::AddPartition(create) =>
                 {
@@ -365,10 +563,24 @@ impl VolumesControl {
                         CreateMessage::PasswordProectedUpdate(protect) => create.password_protected = protect,
                         CreateMessage::EraseUpdate(erase) => create.erase = erase,
                         CreateMessage::PartitionTypeUpdate(p_type) => create.selected_partitition_type = p_type,
-                        CreateMessage::Continue => todo!(),
-                        CreateMessage::Cancel => todo!(),
+                        CreateMessage::FilesystemUpdate(index) => {
+                            create.filesystem = FilesystemType::ALL[index];
+                            if create.filesystem == FilesystemType::Empty {
+                                create.password_protected = false;
+                            }
+                        }
+                        CreateMessage::Continue => {
+                            if create.can_continue {
+                                return Task::done(CreateMessage::Partition(create.clone()).into());
+                            }
+                        }
+                        CreateMessage::Cancel => return Task::done(Message::CloseDialog.into()),
                         CreateMessage::Partition(create_partition_info) =>
                         {
+                            if !create_partition_info.can_continue {
+                                return Task::none();
+                            }
+
                             let model = self.model.clone();
                             let task = Task::perform(
                                         async move {
@@ -394,6 +606,331 @@ impl VolumesControl {
                             return Task::done(Message::CloseDialog.into()).chain(task);
                         }
                     }
+
+                    create.can_continue = create.validate().is_ok();
+                }
+            }
+        }
+        VolumesControlMessage::FormatMessage(format_message) => {
+            let format = match dialog.as_mut()
+            {
+                Some(ShowDialog::FormatPartition(format)) => format,
+                _ => return Task::none(),
+            };
+
+            match format_message {
+                FormatMessage::NameUpdate(name) => format.name = name,
+                FormatMessage::FilesystemUpdate(index) => {
+                    format.filesystem = FilesystemType::ALL[index];
+                    if !format.filesystem.is_posix() {
+                        format.take_ownership = false;
+                    }
+                    if format.filesystem == FilesystemType::Empty {
+                        format.name.clear();
+                        format.password_protected = false;
+                    }
+                }
+                FormatMessage::EraseUpdate(erase) => format.erase = match erase {
+                    true => EraseMode::Zero,
+                    false => EraseMode::None,
+                },
+                FormatMessage::TakeOwnershipUpdate(take) => format.take_ownership = take,
+                FormatMessage::PasswordProtectedUpdate(protect) => format.password_protected = protect,
+                FormatMessage::PasswordUpdate(password) => format.password = password,
+                FormatMessage::ConfirmedPasswordUpdate(confirmed_password) => format.confirmed_password = confirmed_password,
+                FormatMessage::Cancel => return Task::done(Message::CloseDialog.into()),
+                FormatMessage::Format(format_info) => {
+                    let partition = match self.segments.get(self.selected_segment).and_then(|s| s.partition.clone()) {
+                        Some(p) => p,
+                        None => return Task::done(Message::CloseDialog.into()),
+                    };
+
+                    let task = Task::perform(
+                        async move {
+                            match partition.format(format_info).await {
+                                Ok(_) => partition.reload().await,
+                                Err(e) => Err(e),
+                            }
+                        },
+                        |result| match result {
+                            Ok(partition) => Message::PartitionUpdated(partition).into(),
+                            Err(e) => {
+                                println!("{e}");
+                                Message::None.into()
+                            }
+                        },
+                    );
+
+                    return Task::done(Message::CloseDialog.into()).chain(task);
+                }
+            }
+        }
+        VolumesControlMessage::EditMessage(edit_message) => {
+            let edit = match dialog.as_mut()
+            {
+                Some(ShowDialog::EditPartition(edit)) => edit,
+                _ => return Task::none(),
+            };
+
+            match edit_message {
+                EditMessage::FilterUpdate(filter) => edit.filter = filter,
+                EditMessage::TypeSelected(type_id) => edit.type_id = type_id,
+                EditMessage::NameUpdate(name) => edit.name = name,
+                EditMessage::FlagToggled(bit, set) => match set {
+                    true => edit.flags |= bit,
+                    false => edit.flags &= !bit,
+                },
+                EditMessage::Cancel => return Task::done(Message::CloseDialog.into()),
+                EditMessage::Apply(edit_info) => {
+                    let partition = match self.segments.get(self.selected_segment).and_then(|s| s.partition.clone()) {
+                        Some(p) => p,
+                        None => return Task::done(Message::CloseDialog.into()),
+                    };
+
+                    let task = Task::perform(
+                        async move {
+                            match partition.edit_partition(edit_info).await {
+                                Ok(_) => partition.reload().await,
+                                Err(e) => Err(e),
+                            }
+                        },
+                        |result| match result {
+                            Ok(partition) => Message::PartitionUpdated(partition).into(),
+                            Err(e) => {
+                                println!("{e}");
+                                Message::None.into()
+                            }
+                        },
+                    );
+
+                    return Task::done(Message::CloseDialog.into()).chain(task);
+                }
+            }
+        }
+        VolumesControlMessage::FilesystemMessage(filesystem_message) => {
+            let edit = match dialog.as_mut()
+            {
+                Some(ShowDialog::EditFilesystem(edit)) => edit,
+                _ => return Task::none(),
+            };
+
+            match filesystem_message {
+                FilesystemMessage::LabelUpdate(label) => edit.label = label,
+                FilesystemMessage::UuidUpdate(uuid) => edit.uuid = uuid,
+                FilesystemMessage::RegenerateUuidUpdate(regenerate) => edit.regenerate_uuid = regenerate,
+                FilesystemMessage::Cancel => return Task::done(Message::CloseDialog.into()),
+                FilesystemMessage::Apply(edit_info) => {
+                    let partition = match self.segments.get(self.selected_segment).and_then(|s| s.partition.clone()) {
+                        Some(p) => p,
+                        None => return Task::done(Message::CloseDialog.into()),
+                    };
+
+                    let task = Task::perform(
+                        async move {
+                            if edit_info.label != partition.id_label {
+                                partition.edit_filesystem_label(edit_info.label).await?;
+                            }
+
+                            if edit_info.regenerate_uuid {
+                                partition.set_filesystem_uuid(None).await?;
+                            } else if edit_info.uuid != partition.id_uuid {
+                                partition.set_filesystem_uuid(Some(edit_info.uuid)).await?;
+                            }
+
+                            partition.reload().await
+                        },
+                        |result| match result {
+                            Ok(partition) => Message::PartitionUpdated(partition).into(),
+                            Err(e) => {
+                                println!("{e}");
+                                Message::None.into()
+                            }
+                        },
+                    );
+
+                    return Task::done(Message::CloseDialog.into()).chain(task);
+                }
+            }
+        }
+        VolumesControlMessage::ResizeMessage(resize_message) => {
+            let resize = match dialog.as_mut()
+            {
+                Some(ShowDialog::ResizePartition(resize)) => resize,
+                _ => return Task::none(),
+            };
+
+            match resize_message {
+                ResizeMessage::SizeUpdate(size) => resize.size = size.clamp(resize.min_size, resize.max_size),
+                ResizeMessage::Cancel => return Task::done(Message::CloseDialog.into()),
+                ResizeMessage::Resize(resize_info) => {
+                    let partition = match self.segments.get(self.selected_segment).and_then(|s| s.partition.clone()) {
+                        Some(p) => p,
+                        None => return Task::done(Message::CloseDialog.into()),
+                    };
+
+                    let task = Task::perform(
+                        async move {
+                            match partition.resize(resize_info.size).await {
+                                Ok(_) => partition.reload().await,
+                                Err(e) => Err(e),
+                            }
+                        },
+                        |result| match result {
+                            Ok(partition) => Message::PartitionUpdated(partition).into(),
+                            Err(e) => {
+                                println!("{e}");
+                                Message::None.into()
+                            }
+                        },
+                    );
+
+                    return Task::done(Message::CloseDialog.into()).chain(task);
+                }
+            }
+        }
+        VolumesControlMessage::CheckMessage(check_message) => match check_message {
+            CheckMessage::Start(repair) => {
+                let partition = match self.segments.get(self.selected_segment).and_then(|s| s.partition.clone()) {
+                    Some(p) => p,
+                    None => return Task::none(),
+                };
+
+                *dialog = Some(ShowDialog::CheckFilesystem(FilesystemCheck {
+                    name: partition.pretty_name(),
+                    repair,
+                    outcome: None,
+                }));
+
+                return Task::perform(
+                    async move {
+                        match repair {
+                            true => partition.repair_filesystem().await,
+                            false => partition.check_filesystem().await,
+                        }
+                        .map_err(|e| e.to_string())
+                    },
+                    |result| CheckMessage::Finished(result).into(),
+                );
+            }
+            CheckMessage::Finished(result) => {
+                if let Some(ShowDialog::CheckFilesystem(check)) = dialog.as_mut() {
+                    check.outcome = Some(result);
+                }
+
+                //Checking unmounts and remounts the filesystem, so pick up where it ended up.
+                if let Some(partition) = self.segments.get(self.selected_segment).and_then(|s| s.partition.clone()) {
+                    return Task::perform(async move { partition.reload().await }, |result| match result {
+                        Ok(partition) => Message::PartitionUpdated(partition).into(),
+                        Err(e) => {
+                            println!("{e}");
+                            Message::None.into()
+                        }
+                    });
+                }
+            }
+            CheckMessage::Close => return Task::done(Message::CloseDialog.into()),
+        },
+        VolumesControlMessage::OwnershipMessage(ownership_message) => match ownership_message {
+            OwnershipMessage::RecursiveUpdate(recursive) => {
+                if let Some(ShowDialog::TakeOwnership(r)) = dialog.as_mut() {
+                    *r = recursive;
+                }
+            }
+            OwnershipMessage::Cancel => return Task::done(Message::CloseDialog.into()),
+            OwnershipMessage::Apply(recursive) => {
+                let partition = match self.segments.get(self.selected_segment).and_then(|s| s.partition.clone()) {
+                    Some(p) => p,
+                    None => return Task::done(Message::CloseDialog.into()),
+                };
+
+                let task = Task::perform(
+                    async move {
+                        match partition.take_ownership(recursive).await {
+                            Ok(_) => partition.reload().await,
+                            Err(e) => Err(e),
+                        }
+                    },
+                    |result| match result {
+                        Ok(partition) => Message::PartitionUpdated(partition).into(),
+                        Err(e) => {
+                            println!("{e}");
+                            Message::None.into()
+                        }
+                    },
+                );
+
+                return Task::done(Message::CloseDialog.into()).chain(task);
+            }
+        },
+        VolumesControlMessage::MountOptionsMessage(mount_options_message) => {
+            let partition = self.segments.get(self.selected_segment).and_then(|s| s.partition.clone());
+
+            match mount_options_message {
+                MountOptionsMessage::Open => {
+                    let partition = match partition {
+                        Some(p) => p,
+                        None => return Task::none(),
+                    };
+
+                    return Task::perform(async move { partition.mount_options().await }, |result| match result {
+                        Ok(info) => MountOptionsMessage::Loaded(info).into(),
+                        Err(e) => {
+                            println!("{e}");
+                            Message::None.into()
+                        }
+                    });
+                }
+                MountOptionsMessage::Loaded(info) => *dialog = Some(ShowDialog::MountOptions(info)),
+                MountOptionsMessage::Cancel => return Task::done(Message::CloseDialog.into()),
+                MountOptionsMessage::Apply(info) => {
+                    let partition = match partition {
+                        Some(p) => p,
+                        None => return Task::done(Message::CloseDialog.into()),
+                    };
+
+                    let task = Task::perform(
+                        async move {
+                            match partition.edit_mount_options(info).await {
+                                Ok(_) => partition.reload().await,
+                                Err(e) => Err(e),
+                            }
+                        },
+                        |result| match result {
+                            Ok(partition) => Message::PartitionUpdated(partition).into(),
+                            Err(e) => {
+                                println!("{e}");
+                                Message::None.into()
+                            }
+                        },
+                    );
+
+                    return Task::done(Message::CloseDialog.into()).chain(task);
+                }
+                edit => {
+                    let info = match dialog.as_mut()
+                    {
+                        Some(ShowDialog::MountOptions(info)) => info,
+                        _ => return Task::none(),
+                    };
+
+                    match edit {
+                        MountOptionsMessage::UseDefaultsUpdate(v) => info.use_defaults = v,
+                        MountOptionsMessage::MountAtStartupUpdate(v) => info.mount_at_startup = v,
+                        MountOptionsMessage::ShowInUiUpdate(v) => info.show_in_ui = v,
+                        MountOptionsMessage::RequireAuthUpdate(v) => info.require_auth = v,
+                        MountOptionsMessage::DisplayNameUpdate(v) => info.display_name = v,
+                        MountOptionsMessage::IconNameUpdate(v) => info.icon_name = v,
+                        MountOptionsMessage::SymbolicIconNameUpdate(v) => info.symbolic_icon_name = v,
+                        MountOptionsMessage::OptionsUpdate(v) => info.options = v,
+                        MountOptionsMessage::MountPointUpdate(v) => info.mount_point = v,
+                        MountOptionsMessage::IdentifyAsUpdate(index) => {
+                            if let Some(choice) = info.identify_choices.get(index) {
+                                info.identify_as = choice.clone();
+                            }
+                        }
+                        MountOptionsMessage::FilesystemTypeUpdate(v) => info.filesystem_type = v,
+                        _ => {}
+                    }
                 }
             }
         }
@@ -432,19 +969,79 @@ impl VolumesControl {
 
         action_bar.push(match selected.partition {
             Some(p) => {
-                match p.usage //TODO: More solid check than using the output of df to see if mounted.
+                match p.is_mounted()
             {
-                Some(_) => widget::button::custom(icon::from_name( "media-playback-stop-symbolic")).on_press(VolumesControlMessage::Unmount.into()),
-                None =>widget::button::custom(icon::from_name( "media-playback-start-symbolic")).on_press(VolumesControlMessage::Mount.into()),
+                true => widget::button::custom(icon::from_name( "media-playback-stop-symbolic")).on_press(VolumesControlMessage::Unmount.into()),
+                false =>widget::button::custom(icon::from_name( "media-playback-start-symbolic")).on_press(VolumesControlMessage::Mount.into()),
             }
             }
-            None =>widget::button::custom(icon::from_name( "list-add-symbolic")).on_press(Message::Dialog(ShowDialog::AddPartition(selected.get_create_info())).into()),
+            None =>widget::button::custom(icon::from_name( "list-add-symbolic")).on_press(Message::Dialog(ShowDialog::AddPartition(selected.get_create_info(self.model.partition_table_type.as_deref().unwrap_or_default()))).into()),
  
         }.into());
 
         //TODO Get better icons
         if !selected.is_free_space {
-            action_bar.push(widget::button::custom(icon::from_name("edit-find-symbolic")).into());
+            let edit_info = match (&selected.partition, &self.model.partition_table_type) {
+                (Some(p), Some(table_type)) => Some(p.edit_info(table_type)),
+                _ => None,
+            };
+            action_bar.push(
+                widget::button::custom(icon::from_name("edit-find-symbolic"))
+                    .on_press_maybe(edit_info.map(|e| Message::Dialog(ShowDialog::EditPartition(e)).into()))
+                    .into(),
+            );
+            let free_after = match self.segments.get(self.selected_segment + 1) {
+                Some(s) if s.is_free_space => s.size,
+                _ => 0,
+            };
+            let resize_info = selected
+                .partition
+                .as_ref()
+                .map(|p| p.resize_info(free_after))
+                .filter(|r| r.min_size < r.max_size);
+            action_bar.push(
+                widget::button::custom(icon::from_name("zoom-fit-best-symbolic"))
+                    .on_press_maybe(resize_info.map(|r| Message::Dialog(ShowDialog::ResizePartition(r)).into()))
+                    .into(),
+            );
+
+            let can_check = selected.partition.as_ref().is_some_and(|p| p.can_check());
+            action_bar.push(
+                widget::button::custom(icon::from_name("emblem-ok-symbolic"))
+                    .on_press_maybe(can_check.then(|| CheckMessage::Start(false).into()))
+                    .into(),
+            );
+            action_bar.push(
+                widget::button::custom(icon::from_name("applications-engineering-symbolic"))
+                    .on_press_maybe(can_check.then(|| Message::Dialog(ShowDialog::RepairFilesystem(selected.name.clone())).into()))
+                    .into(),
+            );
+
+            let can_take_ownership = selected.partition.as_ref().is_some_and(|p| p.can_take_ownership());
+            action_bar.push(
+                widget::button::custom(icon::from_name("system-users-symbolic"))
+                    .on_press_maybe(can_take_ownership.then(|| Message::Dialog(ShowDialog::TakeOwnership(false)).into()))
+                    .into(),
+            );
+
+            let can_mount = selected.partition.as_ref().is_some_and(|p| p.has_filesystem());
+            action_bar.push(
+                widget::button::custom(icon::from_name("emblem-system-symbolic"))
+                    .on_press_maybe(can_mount.then(|| MountOptionsMessage::Open.into()))
+                    .into(),
+            );
+
+            let filesystem_info = selected.partition.as_ref().and_then(|p| p.edit_filesystem_info());
+            action_bar.push(
+                widget::button::custom(icon::from_name("document-properties-symbolic"))
+                    .on_press_maybe(filesystem_info.map(|e| Message::Dialog(ShowDialog::EditFilesystem(e)).into()))
+                    .into(),
+            );
+            action_bar.push(
+                widget::button::custom(icon::from_name("edit-clear-all-symbolic"))
+                    .on_press(Message::Dialog(ShowDialog::FormatPartition(selected.get_format_info())).into())
+                    .into(),
+            );
             action_bar.push(widget::horizontal_space().into());
             action_bar.push(
                 widget::button::custom(icon::from_name("edit-delete-symbolic"))